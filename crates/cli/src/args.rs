@@ -1,4 +1,4 @@
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
 #[derive(Debug, Parser)]
@@ -10,4 +10,52 @@ pub struct SolisArgs {
     /// The path of the file to run (optional)
     #[arg(short, long)]
     pub file_path: Option<PathBuf>,
+    /// The backend used to execute code
+    #[arg(short, long, value_enum, default_value_t = Backend::Tree)]
+    pub backend: Backend,
+    /// Run a syntax tree serialized as JSON (as printed by `ast --json`) instead of source
+    #[arg(long, requires = "file_path")]
+    pub json: bool,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Print the syntax tree of a file as S-expressions
+    Ast {
+        /// The path of the file to parse
+        file_path: PathBuf,
+        /// Print the syntax tree as JSON, including spans and literal values
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the tokens of a file
+    Tokens {
+        /// The path of the file to lex
+        file_path: PathBuf,
+        /// Print the tokens as JSON
+        #[arg(long)]
+        json: bool,
+        /// Also print the whitespace and comments around each token
+        #[arg(long)]
+        trivia: bool,
+    },
+    /// Format files in place
+    Fmt {
+        /// The paths of the files to format
+        #[arg(required = true)]
+        file_paths: Vec<PathBuf>,
+        /// Only check that the files are formatted, exiting with an error if not
+        #[arg(long)]
+        check: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Backend {
+    /// The tree-walking interpreter
+    Tree,
+    /// The bytecode compiler and stack-based VM
+    Vm,
 }
@@ -1,49 +1,228 @@
-use crate::utils::{input, read_file};
+use crate::{
+    args::Backend,
+    utils::{input, read_file},
+};
+use formatter::Formatter;
 use interpreter::Interpreter;
 use lexer::Lexer;
 use log::{debug, error};
 use parser::Parser;
-use std::{path::PathBuf, rc::Rc};
+use resolver::Resolver;
+use solis_core::{
+    ast_from_json, ast_to_json, AstPrinter, Diagnostic, SolisError, Span, Stmt, Trivia,
+};
+use std::path::PathBuf;
+use vm::Vm;
 
-pub fn run(file_path: PathBuf) {
-    let code = read_file(&file_path);
-    let Ok(code) = code else {
-        return error!("Could not read file at path {:?}", file_path);
+pub enum Runtime {
+    Tree(Interpreter),
+    Vm(Vm),
+}
+
+impl Runtime {
+    pub fn new(backend: Backend) -> Self {
+        match backend {
+            Backend::Tree => Self::Tree(Interpreter::new()),
+            Backend::Vm => Self::Vm(Vm::new()),
+        }
+    }
+}
+
+fn read_source(file_path: &PathBuf) -> Option<String> {
+    let code = read_file(file_path);
+    if code.is_err() {
+        error!("Could not read file at path {:?}", file_path);
+    }
+    code.ok()
+}
+
+pub fn run(file_path: PathBuf, backend: Backend) {
+    let Some(code) = read_source(&file_path) else {
+        return;
     };
 
-    run_code(code, Rc::new(Interpreter::new()))
+    run_code(
+        code,
+        &file_path.to_string_lossy(),
+        &mut Runtime::new(backend),
+    )
 }
 
-pub fn repl() {
-    let interpreter = Rc::new(Interpreter::new());
+/// Runs a syntax tree serialized as JSON. There's no source to point errors into, so
+/// they only show their location.
+pub fn run_json(file_path: PathBuf, backend: Backend) {
+    let Some(json) = read_source(&file_path) else {
+        return;
+    };
+    let file_name = file_path.to_string_lossy();
+
+    match ast_from_json(&json) {
+        Ok(statements) => run_statements(statements, "", &file_name, &mut Runtime::new(backend)),
+        Err(err) => error!(
+            "Could not read syntax tree at path {:?}: {}",
+            file_path, err
+        ),
+    }
+}
+
+pub fn repl(backend: Backend) {
+    let mut runtime = Runtime::new(backend);
     loop {
         let code = input("> ").unwrap();
-        let code = code.replace("\\n", "\n");
 
         if code == ":exit" {
             break;
         }
 
-        run_code(code, interpreter.clone())
+        run_code(code, "<repl>", &mut runtime)
     }
 }
 
-pub fn run_code(code: String, interpreter: Rc<Interpreter>) {
-    let tokens_raw = Lexer::lex(code.as_str());
-    let Ok(tokens) = tokens_raw else {
-        return error!("{}", tokens_raw.err().unwrap());
+/// Prints the syntax tree of the file at `file_path`, without running it.
+pub fn ast(file_path: PathBuf, json: bool) {
+    let Some(code) = read_source(&file_path) else {
+        return;
     };
+    let file_name = file_path.to_string_lossy();
+
+    let (tokens, errors) = Lexer::lex(&code);
+    if report_all(&errors, &code, &file_name) {
+        return;
+    }
+    let (statements, errors) = Parser::new(tokens).parse();
+    if report_all(&errors, &code, &file_name) {
+        return;
+    }
+    if !json {
+        return print!("{}", AstPrinter::print(&statements));
+    }
+    match ast_to_json(&statements) {
+        Ok(json) => println!("{}", json),
+        Err(err) => report(&err, &code, &file_name),
+    }
+}
 
-    let parsed = Parser::new(tokens).parse();
-    let Ok(parsed_expr) = parsed else {
-        return error!("{}", parsed.err().unwrap());
+/// Prints every token of the file at `file_path` with its position and type. With
+/// `trivia`, the whitespace and comments around each token are printed as well.
+pub fn tokens(file_path: PathBuf, json: bool, trivia: bool) {
+    let Some(code) = read_source(&file_path) else {
+        return;
     };
 
+    let (tokens, errors) = Lexer::lex_lossless(&code);
+    // Lexed literals are always plain values, which serialize fine.
+    match (json, trivia) {
+        (true, true) => println!("{}", serde_json::to_string_pretty(&tokens).unwrap()),
+        (true, false) => {
+            let tokens: Vec<_> = tokens.iter().map(|token| &token.token).collect();
+            println!("{}", serde_json::to_string_pretty(&tokens).unwrap())
+        }
+        (false, _) => {
+            for token in tokens {
+                if trivia {
+                    token.leading.iter().for_each(print_trivia);
+                }
+                let ty = format!("{:?}", token.token.ty);
+                print_row(token.token.span, &ty, &token.token.lexeme);
+                if trivia {
+                    token.trailing.iter().for_each(print_trivia);
+                }
+            }
+        }
+    }
+    report_all(&errors, &code, &file_path.to_string_lossy());
+}
+
+/// Prints a line of the `tokens` listing.
+fn print_row(span: Span, ty: &str, text: &str) {
+    let position = format!("{}:{}", span.line, span.column);
+    println!("{:<8} {:<14} {}", position, ty, text);
+}
+
+fn print_trivia(trivia: &Trivia) {
+    let kind = format!("{:?}", trivia.kind);
+    print_row(trivia.span, &kind, &format!("{:?}", trivia.text));
+}
+
+/// Formats each file in place, or with `check` only lists the ones that aren't formatted.
+/// Returns whether every file was left formatted.
+pub fn fmt(file_paths: Vec<PathBuf>, check: bool) -> bool {
+    let mut formatted = true;
+    for file_path in file_paths {
+        let Some(code) = read_source(&file_path) else {
+            formatted = false;
+            continue;
+        };
+        let file_name = file_path.to_string_lossy();
+
+        let output = match Formatter::format(&code) {
+            Ok(output) => output,
+            Err(errors) => {
+                report_all(&errors, &code, &file_name);
+                formatted = false;
+                continue;
+            }
+        };
+        if output == code {
+            continue;
+        }
+
+        if check {
+            println!("{} is not formatted", file_name);
+            formatted = false;
+        } else if std::fs::write(&file_path, output).is_err() {
+            error!("Could not write file at path {:?}", file_path);
+            formatted = false;
+        }
+    }
+    formatted
+}
+
+/// Prints `err` as a diagnostic pointing into `code`.
+fn report(err: &SolisError, code: &str, file_name: &str) {
+    eprintln!("{}\n", Diagnostic::from(err).render(code, file_name));
+}
+
+/// Reports every error in `errors`, returning whether there were any.
+fn report_all(errors: &[SolisError], code: &str, file_name: &str) -> bool {
+    errors.iter().for_each(|err| report(err, code, file_name));
+    !errors.is_empty()
+}
+
+pub fn run_code(code: String, file_name: &str, runtime: &mut Runtime) {
+    let (tokens, errors) = Lexer::lex(code.as_str());
+    if report_all(&errors, &code, file_name) {
+        return;
+    }
+
+    let (parsed_expr, errors) = Parser::new(tokens).parse();
+    if report_all(&errors, &code, file_name) {
+        return;
+    }
+
     debug!("Parsed code");
 
-    let interpreted_r = interpreter.interpret(parsed_expr);
+    run_statements(parsed_expr, &code, file_name, runtime)
+}
+
+/// Resolves and runs a parsed program, reporting errors against `code`.
+#[allow(clippy::vec_box)] // The program as the parser hands it to the backends.
+fn run_statements(
+    mut statements: Vec<Box<Stmt>>,
+    code: &str,
+    file_name: &str,
+    runtime: &mut Runtime,
+) {
+    if report_all(&Resolver::resolve(&mut statements), code, file_name) {
+        return;
+    }
 
-    let Ok(_interpreter) = interpreted_r else {
-        return error!("{}", interpreted_r.err().unwrap())
+    let interpreted_r = match runtime {
+        Runtime::Tree(interpreter) => interpreter.interpret(statements),
+        Runtime::Vm(vm) => vm.interpret(statements),
     };
+
+    if let Err(err) = interpreted_r {
+        report(&err, code, file_name)
+    }
 }
@@ -33,8 +33,23 @@ fn main() {
         })
         .init();
 
-    match args.file_path {
-        Some(file_path) => run(file_path),
-        _ => repl(),
+    match (args.command, args.file_path) {
+        (Some(Command::Ast { file_path, json }), _) => ast(file_path, json),
+        (
+            Some(Command::Tokens {
+                file_path,
+                json,
+                trivia,
+            }),
+            _,
+        ) => tokens(file_path, json, trivia),
+        (Some(Command::Fmt { file_paths, check }), _) => {
+            if !fmt(file_paths, check) {
+                std::process::exit(1)
+            }
+        }
+        (None, Some(file_path)) if args.json => run_json(file_path, args.backend),
+        (None, Some(file_path)) => run(file_path, args.backend),
+        (None, None) => repl(args.backend),
     }
 }
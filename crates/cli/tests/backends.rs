@@ -0,0 +1,428 @@
+use interpreter::Interpreter;
+use lexer::Lexer;
+use parser::Parser;
+use resolver::Resolver;
+use solis_core::*;
+use std::{cell::RefCell, path::PathBuf, process::Command, rc::Rc};
+use vm::Vm;
+
+/// Runs `source` with the given backend and returns everything it printed.
+fn run(name: &str, source: &str, backend: &str) -> String {
+    let path: PathBuf = std::env::temp_dir().join(format!("solis-{}.hl", name));
+    std::fs::write(&path, source).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_cli"))
+        .args(["-b", backend, "-f"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    String::from_utf8_lossy(&output.stdout).into_owned() + &String::from_utf8_lossy(&output.stderr)
+}
+
+/// Asserts that both backends print `expected` for `source`.
+fn assert_backends(name: &str, source: &str, expected: &str) {
+    assert_eq!(run(name, source, "tree"), expected, "tree backend");
+    assert_eq!(run(name, source, "vm"), expected, "vm backend");
+}
+
+#[test]
+fn compound_index_assignment_reads_the_element_first() {
+    assert_backends(
+        "compound-index",
+        r#"
+var xs = [1];
+fn f() { xs[0] = 100; return 1; }
+xs[0] += f();
+print xs;
+
+var m = {"a": 1};
+fn g() { m["a"] = 50; return 1; }
+m["a"] += g();
+print m;
+
+var o = 1;
+fn h() { o = 10; return 1; }
+o += h();
+print o;
+"#,
+        "[2]\n{\"a\": 2}\n2\n",
+    );
+}
+
+/// Programs both backends must run the same way, one per language feature.
+const PROGRAMS: &[(&str, &str)] = &[
+    ("example", include_str!("../../../example.hl")),
+    (
+        "closures",
+        r#"
+fn counter() {
+  var count = 0;
+  fn increment() { count = count + 1; return count; }
+  return increment;
+}
+var a = counter();
+var b = counter();
+print a();
+print a();
+print b();
+var adders = [nil, nil, nil];
+for (var i = 0; i < 3; i = i + 1) {
+  var j = i;
+  adders[i] = |x| x + j;
+}
+print adders[2](10);
+"#,
+    ),
+    (
+        "classes",
+        r#"
+class Shape {
+  init(name) { this.name = name; }
+  describe() { return this.name + " with area " + this.area(); }
+  area() { return 0; }
+}
+class Square < Shape {
+  init(side) { super.init("square"); this.side = side; }
+  area() { return this.side * this.side; }
+  describe() { return "A " + super.describe(); }
+}
+var square = Square(3);
+print square.describe();
+var describe = square.describe;
+print describe();
+print square;
+print Square;
+"#,
+    ),
+    (
+        "arguments",
+        r#"
+fn greet(name, greeting = "Hello", ...rest) {
+  return "${greeting}, ${name}! ${len(rest)} more";
+}
+print greet("Ada");
+print greet("Ada", "Hi");
+print greet("Ada", greeting: "Hey");
+print greet("Ada", "Yo", 1, 2, 3);
+var f = fn (x, y = x * 2) { return [x, y]; };
+print f(1);
+print f(1, y: 5);
+"#,
+    ),
+    (
+        "maps",
+        r#"
+var m = {"a": 1, 2: "two", true: nil};
+m["b"] = [1, 2];
+m[2.0] = "float two";
+print m;
+print len(m);
+print keys(m);
+delete(m, "a");
+print m["a"];
+print m == {2: "float two", true: nil, "b": [1, 2]};
+"#,
+    ),
+    (
+        "indices",
+        r#"
+var xs = [1, 2, 3];
+xs[0] = 10;
+xs[-1] += 5;
+print xs;
+print xs[-2];
+var grid = [[1, 2], [3, 4]];
+grid[1][0] *= 10;
+print grid;
+print xs[3];
+"#,
+    ),
+    (
+        "interpolation",
+        r#"
+var name = "world";
+print "hello ${name}";
+print "${1 + 2} and ${ "nested ${name}" }";
+print r"raw ${name}\n";
+print "tab\tquote\" done";
+"#,
+    ),
+    (
+        "numbers",
+        r#"
+print 7 / 2;
+print 7 ~/ 2;
+print -7 % 3;
+print 0x1F + 0b101 + 0o17 + 1_000;
+print 1.5e3;
+print 123456789n * 987654321987654321n;
+print 0.1d + 0.2d;
+print 1 == 1.0;
+print 12n == 12;
+print int("42") + float("1.5");
+print bigint("1e30");
+print decimal("1.25") * 2;
+print 9223372036854775807 < 9223372036854775808.0;
+print 9223372036854775807 + 1;
+"#,
+    ),
+    (
+        "runtime-error",
+        "var x = 1;\nprint x;\nprint x - \"a\";\nprint \"unreachable\";\n",
+    ),
+    ("undefined-variable", "print missing;\n"),
+    ("arity-error", "fn f(a) {}\nf(1, 2);\n"),
+];
+
+#[test]
+fn backends_agree_on_every_feature() {
+    for (name, source) in PROGRAMS {
+        // example.hl times itself, so the line after "elapsed:" differs between runs.
+        let run = |backend| {
+            let mut lines: Vec<_> = run(name, source, backend)
+                .lines()
+                .map(String::from)
+                .collect();
+            if let Some(i) = lines.iter().position(|line| line == "\"elapsed:\"") {
+                lines.truncate(i + 1);
+            }
+            lines
+        };
+        let tree = run("tree");
+        assert!(!tree.is_empty(), "{} printed nothing", name);
+        assert_eq!(tree, run("vm"), "{}", name);
+    }
+}
+
+#[test]
+fn syntax_errors_render_as_diagnostics() {
+    let path = std::env::temp_dir().join("solis-diagnostic.hl");
+    assert_backends(
+        "diagnostic",
+        "var x = 1;\nif x > 0) print x;\n",
+        &format!(
+            "error: expected '(' after 'if'\n --> {}:2:4\n  |\n2 | if x > 0) print x;\n  |    ^\n\n",
+            path.display()
+        ),
+    );
+}
+
+#[allow(clippy::vec_box)] // The program as the parser hands it to the backends.
+fn program(source: &str) -> Vec<Box<Stmt>> {
+    let (tokens, errors) = Lexer::lex(source);
+    assert!(errors.is_empty(), "{:?}", errors);
+    let (mut statements, errors) = Parser::new(tokens).parse();
+    assert!(errors.is_empty(), "{:?}", errors);
+    let errors = Resolver::resolve(&mut statements);
+    assert!(errors.is_empty(), "{:?}", errors);
+    statements
+}
+
+/// Runs `source` on an embedded backend given `apply(f, x)`, which calls the script
+/// function `f` with `x`, `attempt(f, x)`, which does the same but gives `nil` if the
+/// call fails, and `record(x)`, which logs `x`. Returns the log and the error the
+/// script ended with, if any.
+macro_rules! embedded {
+    ($backend:expr, $source:expr) => {{
+        let mut backend = $backend;
+        let log = Rc::new(RefCell::new(Vec::new()));
+        backend.register_fn("apply", 2, |arguments| {
+            let function: Rc<dyn Callable> = arguments.get(0)?;
+            function.call(vec![arguments.get(1)?])
+        });
+        backend.register_fn("attempt", 2, |arguments| {
+            let function: Rc<dyn Callable> = arguments.get(0)?;
+            Ok(function
+                .call(vec![arguments.get(1)?])
+                .unwrap_or(ValueWrapper::Nil))
+        });
+        let record = log.clone();
+        backend.register_fn("record", 1, move |arguments| {
+            let value: ValueWrapper = arguments.get(0)?;
+            record.borrow_mut().push(value.to_string());
+            Ok(ValueWrapper::Nil)
+        });
+        let result = backend.interpret(program($source));
+        let log = log.borrow().clone();
+        (log, result.err().map(|err| err.to_string()))
+    }};
+}
+
+#[test]
+fn natives_can_call_script_functions() {
+    let source = r#"
+fn double(x) { return x * 2; }
+record(apply(double, 21));
+record(apply(|x| x + 1, 1));
+record(apply(|x| apply(double, x), 4));
+
+class A {
+  init(n) { this.n = n; }
+  add(k) { return this.n + k; }
+}
+record(apply(A(10).add, 5));
+
+var count = 0;
+fn bump(by) { count = count + by; return count; }
+apply(bump, 3);
+record(count);
+
+fn fail(x) { var local = x; return local - "a"; }
+record(attempt(fail, 1));
+record(apply(double, 5));
+record("before");
+apply(fail, 1);
+record("unreachable");
+"#;
+    let tree = embedded!(Interpreter::new(), source);
+    let vm = embedded!(Vm::new(), source);
+    assert_eq!(
+        tree.0,
+        ["42", "2", "8", "15", "3", "nil", "10", "\"before\""]
+    );
+    assert!(tree.1.is_some());
+    assert_eq!(tree, vm);
+}
+
+#[test]
+fn integers_and_floats_agree_at_the_edge_of_i64() {
+    assert_backends(
+        "i64-edge",
+        r#"
+var max = 9223372036854775807;
+var float = 9223372036854775808.0;
+print max == float;
+print float == max;
+print max < float;
+print float > max;
+var m = {};
+m[max] = "int";
+m[float] = "float";
+print len(m);
+print m[max];
+print m[float];
+"#,
+        "false\nfalse\ntrue\ntrue\n2\n\"int\"\n\"float\"\n",
+    );
+}
+
+#[test]
+fn continue_runs_the_for_increment() {
+    assert_backends(
+        "for-continue",
+        r#"
+for (var i = 0; i < 5; i = i + 1) {
+  if (i % 2 == 0) continue;
+  print i;
+}
+var j = 0;
+while (j < 3) {
+  j = j + 1;
+  if (j == 2) continue;
+  print j;
+}
+for (var k = 0; k < 3; k = k + 1) {
+  var captured = k;
+  if (k == 1) continue;
+  print fn () { return captured; }();
+}
+"#,
+        "1\n3\n1\n3\n0\n2\n",
+    );
+}
+
+#[test]
+fn lists_that_hold_themselves_print_and_compare() {
+    assert_backends(
+        "cyclic-list",
+        r#"
+var a = [1];
+a[0] = a;
+print a;
+var b = [1, 2];
+b[1] = b;
+print [b, b];
+var c = [1];
+c[0] = c;
+print a == c;
+print [a] == [c];
+print b == a;
+"#,
+        "[[...]]\n[[1, [...]], [1, [...]]]\ntrue\ntrue\nfalse\n",
+    );
+}
+
+#[test]
+fn maps_that_hold_themselves_print_and_compare() {
+    assert_backends(
+        "cyclic-map",
+        r#"
+var m = {};
+m["self"] = m;
+print m;
+var n = {};
+n["self"] = n;
+print m == n;
+m["list"] = [m];
+print m;
+print m == n;
+"#,
+        "{\"self\": {...}}\ntrue\n{\"self\": {...}, \"list\": [{...}]}\nfalse\n",
+    );
+}
+
+/// Runs a script on one backend that hands the host some functions to keep, calls them
+/// once the script is done, then runs a script on a second backend that calls them
+/// again. Returns what each call gave.
+macro_rules! kept {
+    ($new:expr) => {{
+        let kept: Rc<RefCell<Vec<Rc<dyn Callable>>>> = Rc::default();
+        let mut first = $new;
+        let keep = kept.clone();
+        first.register_fn("keep", 1, move |arguments| {
+            keep.borrow_mut().push(arguments.get(0)?);
+            Ok(ValueWrapper::Nil)
+        });
+        first
+            .interpret(program(
+                r#"
+var x = "first";
+fn make() {
+  var count = 0;
+  return fn () { count = count + 1; return "${x} ${count}"; };
+}
+keep(make());
+keep(|y| y * 2);
+"#,
+            ))
+            .unwrap();
+
+        let mut results = vec![
+            kept.borrow()[0].call(Vec::new()).unwrap().to_string(),
+            kept.borrow()[1].call(vec![21.into()]).unwrap().to_string(),
+        ];
+
+        let mut second = $new;
+        let call = kept.clone();
+        second.register_fn("call_kept", 0, move |_| call.borrow()[0].call(Vec::new()));
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let record = log.clone();
+        second.register_fn("record", 1, move |arguments| {
+            let value: ValueWrapper = arguments.get(0)?;
+            record.borrow_mut().push(value.to_string());
+            Ok(ValueWrapper::Nil)
+        });
+        second
+            .interpret(program("var x = \"second\";\nrecord(call_kept());\n"))
+            .unwrap();
+        results.extend(log.borrow().iter().cloned());
+        results
+    }};
+}
+
+#[test]
+fn kept_functions_run_on_the_backend_that_made_them() {
+    let expected = ["\"first 1\"", "42", "\"first 2\""];
+    assert_eq!(kept!(Interpreter::new()), expected);
+    assert_eq!(kept!(Vm::new()), expected);
+}
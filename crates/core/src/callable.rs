@@ -1,10 +1,102 @@
 use crate::*;
-use std::fmt::{Debug, Display};
+use std::{
+    any::Any,
+    fmt::{Debug, Display},
+    ops::{RangeFrom, RangeInclusive},
+    rc::Rc,
+};
 
-pub trait Callable {
+/// The number of arguments a callable accepts, from `min` up to `max` inclusive. A
+/// missing `max` means any number of extra arguments are accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Arity {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl Arity {
+    pub fn exact(count: usize) -> Self {
+        Self {
+            min: count,
+            max: Some(count),
+        }
+    }
+
+    pub fn range(min: usize, max: usize) -> Self {
+        Self {
+            min,
+            max: Some(max),
+        }
+    }
+
+    pub fn at_least(min: usize) -> Self {
+        Self { min, max: None }
+    }
+
+    pub fn is_variadic(&self) -> bool {
+        self.max.is_none()
+    }
+
+    pub fn accepts(&self, count: usize) -> bool {
+        count >= self.min && self.max.is_none_or(|max| count <= max)
+    }
+}
+
+impl Default for Arity {
+    fn default() -> Self {
+        Self::exact(0)
+    }
+}
+
+impl From<usize> for Arity {
+    fn from(count: usize) -> Self {
+        Self::exact(count)
+    }
+}
+
+impl From<RangeInclusive<usize>> for Arity {
+    fn from(range: RangeInclusive<usize>) -> Self {
+        Self::range(*range.start(), *range.end())
+    }
+}
+
+impl From<RangeFrom<usize>> for Arity {
+    fn from(range: RangeFrom<usize>) -> Self {
+        Self::at_least(range.start)
+    }
+}
+
+impl Display for Arity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => f.write_fmt(format_args!("{}", max)),
+            Some(max) => f.write_fmt(format_args!("{} to {}", self.min, max)),
+            None => f.write_fmt(format_args!("at least {}", self.min)),
+        }
+    }
+}
+
+pub trait Callable: Any {
     fn name(&self) -> &str;
-    fn arity(&self) -> usize;
-    fn call(&self, arguments: Vec<ValueWrapper>, env: EnvData) -> Result<ValueWrapper>;
+    fn arity(&self) -> Arity;
+    fn call(&self, arguments: Vec<ValueWrapper>) -> Result<ValueWrapper>;
+    /// Calls with named arguments following the positional ones. Only script functions
+    /// know their parameter names, so by default any named argument is an error.
+    fn call_named(
+        &self,
+        arguments: Vec<ValueWrapper>,
+        named: Vec<(String, ValueWrapper)>,
+    ) -> Result<ValueWrapper> {
+        if !named.is_empty() {
+            return Err(SolisError::RuntimeError(
+                Span::default(),
+                format!("`{}` doesn't accept named arguments.", self.name()),
+            ));
+        }
+        self.call(arguments)
+    }
+    /// Returns this callable with `this` bound to `instance`, for use as a method.
+    fn bind(self: Rc<Self>, instance: ValueWrapper) -> Rc<dyn Callable>;
 }
 
 impl Display for dyn Callable {
@@ -19,22 +111,203 @@ impl Debug for dyn Callable {
     }
 }
 
+/// Matches the arguments of a call to `params`, first by position and then by name.
+/// Returns the value for each parameter, `None` where the caller left out one that
+/// has a default, and the positional arguments beyond the last parameter. The first
+/// `required` parameters have no default.
+pub fn bind_arguments<'a>(
+    function: &str,
+    params: impl ExactSizeIterator<Item = &'a str> + Clone,
+    required: usize,
+    mut arguments: Vec<ValueWrapper>,
+    named: Vec<(String, ValueWrapper)>,
+) -> Result<(Vec<Option<ValueWrapper>>, Vec<ValueWrapper>)> {
+    let error = |message| Err(SolisError::RuntimeError(Span::default(), message));
+
+    let extra = arguments.split_off(params.len().min(arguments.len()));
+    let mut slots: Vec<_> = arguments.into_iter().map(Some).collect();
+    slots.resize(params.len(), None);
+
+    for (name, value) in named {
+        let Some(index) = params.clone().position(|param| param == name) else {
+            return error(format!("`{}` has no parameter named `{}`.", function, name));
+        };
+        if slots[index].is_some() {
+            return error(format!(
+                "`{}` received more than one value for parameter `{}`.",
+                function, name
+            ));
+        }
+        slots[index] = Some(value);
+    }
+
+    if let Some(index) = slots[..required].iter().position(Option::is_none) {
+        return error(format!(
+            "`{}` is missing a value for parameter `{}`.",
+            function,
+            params.clone().nth(index).unwrap_or_default()
+        ));
+    }
+    Ok((slots, extra))
+}
+
 // Native functions
 
-pub struct NativeFuncClock;
-impl Callable for NativeFuncClock {
-    fn name(&self) -> &str {
-        "clock"
+/// The arguments passed to a [`NativeFunction`], with typed accessors.
+pub struct Arguments<'a> {
+    function: &'a str,
+    values: Vec<ValueWrapper>,
+}
+
+impl Arguments<'_> {
+    /// Converts the argument at `index` into `T`, or errors naming the expected type.
+    /// An argument left out of an optional-arity call reads as `nil`, so it can be
+    /// extracted as an `Option`.
+    pub fn get<T: FromValue>(&self, index: usize) -> Result<T> {
+        let Some(value) = self.values.get(index) else {
+            return T::from_value(&ValueWrapper::Nil).ok_or_else(|| {
+                SolisError::RuntimeError(
+                    Span::default(),
+                    format!(
+                        "`{}` expects at least {} arguments.",
+                        self.function,
+                        index + 1
+                    ),
+                )
+            });
+        };
+
+        T::from_value(value).ok_or_else(|| {
+            SolisError::RuntimeError(
+                Span::default(),
+                format!(
+                    "`{}` expects argument {} to be {}, found `{}`.",
+                    self.function,
+                    index + 1,
+                    T::expected(),
+                    value
+                ),
+            )
+        })
+    }
+
+    pub fn values(&self) -> &[ValueWrapper] {
+        &self.values
+    }
+
+    pub fn into_values(self) -> Vec<ValueWrapper> {
+        self.values
+    }
+}
+
+/// A Rust type a script value can be extracted as.
+pub trait FromValue: Sized {
+    /// Describes the accepted values for error messages, e.g. "a number".
+    fn expected() -> String;
+    fn from_value(value: &ValueWrapper) -> Option<Self>;
+}
+
+macro_rules! from_value {
+    ($($ty:ty => $expected:literal, $variant:ident($value:ident) => $result:expr;)*) => {
+        $(impl FromValue for $ty {
+            fn expected() -> String {
+                String::from($expected)
+            }
+
+            fn from_value(value: &ValueWrapper) -> Option<Self> {
+                match value {
+                    ValueWrapper::$variant($value) => Some($result),
+                    _ => None,
+                }
+            }
+        })*
+    };
+}
+
+from_value! {
+    i64 => "an integer", Int(value) => *value;
+    String => "a string", Str(value) => value.clone();
+    bool => "a boolean", Bool(value) => *value;
+    Rc<dyn Callable> => "a function", Func(value) => value.clone();
+    RcCell<Vec<ValueWrapper>> => "a list", List(value) => value.clone();
+    RcCell<SolisMap> => "a map", Map(value) => value.clone();
+    RcCell<SolisInstance> => "an instance", Instance(value) => value.clone();
+}
+
+/// Every number converts to a float, so a native taking an `f64` accepts any number.
+impl FromValue for f64 {
+    fn expected() -> String {
+        String::from("a number")
+    }
+
+    fn from_value(value: &ValueWrapper) -> Option<Self> {
+        value.to_float()
+    }
+}
+
+impl FromValue for ValueWrapper {
+    fn expected() -> String {
+        String::from("a value")
+    }
+
+    fn from_value(value: &ValueWrapper) -> Option<Self> {
+        Some(value.clone())
     }
+}
 
-    fn arity(&self) -> usize {
-        0
+impl<T: FromValue> FromValue for Option<T> {
+    fn expected() -> String {
+        format!("{} or nil", T::expected())
     }
-    fn call(&self, _arguments: Vec<ValueWrapper>, _env: EnvData) -> Result<ValueWrapper> {
-        if let Ok(n) = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
-            Ok(ValueWrapper::Num(n.as_secs_f64()))
-        } else {
-            panic!("SystemTime before UNIX_EPOCH.");
+
+    fn from_value(value: &ValueWrapper) -> Option<Self> {
+        match value {
+            ValueWrapper::Nil => Some(None),
+            value => T::from_value(value).map(Some),
         }
     }
 }
+
+type NativeFn = dyn Fn(Arguments) -> Result<ValueWrapper>;
+
+/// A function implemented in Rust.
+pub struct NativeFunction {
+    name: String,
+    arity: Arity,
+    function: Box<NativeFn>,
+}
+
+impl NativeFunction {
+    pub fn new<A, F>(name: &str, arity: A, function: F) -> Rc<Self>
+    where
+        A: Into<Arity>,
+        F: Fn(Arguments) -> Result<ValueWrapper> + 'static,
+    {
+        Rc::new(Self {
+            name: name.to_string(),
+            arity: arity.into(),
+            function: Box::new(function),
+        })
+    }
+}
+
+impl Callable for NativeFunction {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn arity(&self) -> Arity {
+        self.arity
+    }
+
+    fn call(&self, arguments: Vec<ValueWrapper>) -> Result<ValueWrapper> {
+        (self.function)(Arguments {
+            function: &self.name,
+            values: arguments,
+        })
+    }
+
+    fn bind(self: Rc<Self>, _instance: ValueWrapper) -> Rc<dyn Callable> {
+        self
+    }
+}
@@ -0,0 +1,95 @@
+use crate::*;
+use rustc_hash::FxHashMap;
+use std::{cell::RefCell, fmt::Debug, rc::Rc};
+
+#[derive(Debug)]
+pub struct SolisClass {
+    pub name: String,
+    pub superclass: Option<Rc<SolisClass>>,
+    methods: FxHashMap<String, Rc<dyn Callable>>,
+}
+
+impl SolisClass {
+    pub fn new(
+        name: String,
+        superclass: Option<Rc<SolisClass>>,
+        methods: FxHashMap<String, Rc<dyn Callable>>,
+    ) -> Rc<Self> {
+        Rc::new(Self {
+            name,
+            superclass,
+            methods,
+        })
+    }
+
+    pub fn find_method(&self, name: &str) -> Option<Rc<dyn Callable>> {
+        if let Some(method) = self.methods.get(name) {
+            return Some(method.clone());
+        }
+
+        self.superclass.as_ref()?.find_method(name)
+    }
+
+    pub fn arity(&self) -> Arity {
+        self.find_method("init")
+            .map_or(Arity::exact(0), |init| init.arity())
+    }
+
+    /// Creates a new instance of `class`, running its initializer (if any) with `arguments`
+    /// and `named`.
+    pub fn instantiate(
+        class: &Rc<Self>,
+        arguments: Vec<ValueWrapper>,
+        named: Vec<(String, ValueWrapper)>,
+    ) -> Result<ValueWrapper> {
+        let instance = ValueWrapper::Instance(SolisInstance::new(class.clone()));
+
+        if let Some(init) = class.find_method("init") {
+            init.bind(instance.clone()).call_named(arguments, named)?;
+        }
+
+        Ok(instance)
+    }
+}
+
+pub struct SolisInstance {
+    pub class: Rc<SolisClass>,
+    fields: FxHashMap<String, ValueWrapper>,
+}
+
+impl SolisInstance {
+    pub fn new(class: Rc<SolisClass>) -> RcCell<Self> {
+        Rc::new(RefCell::new(Self {
+            class,
+            fields: FxHashMap::default(),
+        }))
+    }
+
+    pub fn get(instance: &RcCell<Self>, name: &str, span: Span) -> Result<ValueWrapper> {
+        if let Some(value) = instance.borrow().fields.get(name) {
+            return Ok(value.clone());
+        }
+
+        let method = instance.borrow().class.find_method(name);
+        if let Some(method) = method {
+            return Ok(ValueWrapper::Func(
+                method.bind(ValueWrapper::Instance(instance.clone())),
+            ));
+        }
+
+        Err(SolisError::RuntimeError(
+            span,
+            format!("Undefined property `{}`.", name),
+        ))
+    }
+
+    pub fn set(&mut self, name: &str, value: ValueWrapper) {
+        self.fields.insert(name.to_string(), value);
+    }
+}
+
+impl Debug for SolisInstance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("[<instance (\"{}\")>]", self.class.name))
+    }
+}
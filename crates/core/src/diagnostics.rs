@@ -0,0 +1,86 @@
+use crate::{SolisError, Span};
+use colored::Colorize;
+use std::fmt::Write;
+
+/// A report about a span of source code, rendered rustc-style with the offending line
+/// and an underline beneath the span.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Span,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new<T: Into<String>>(message: T, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span,
+            help: None,
+        }
+    }
+
+    pub fn with_help<T: Into<String>>(mut self, help: T) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// Renders the diagnostic against `source`, the code the span points into, naming
+    /// it `file_name` in the location line.
+    pub fn render(&self, source: &str, file_name: &str) -> String {
+        let start = self.span.start.min(source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |i| start + i);
+        let line = source[line_start..line_end].trim_end_matches('\r');
+
+        // Mirror tabs so the underline stays aligned with the source line.
+        let padding: String = source[line_start..start]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let end = self.span.end.min(line_start + line.len()).max(start);
+        let width = source[start..end].chars().count().max(1);
+
+        let line_number = self.span.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+        let bar = "|".blue().bold();
+
+        let mut out = String::new();
+        let _ = writeln!(out, "{}: {}", "error".red().bold(), self.message.bold());
+        let _ = writeln!(
+            out,
+            "{}{} {}:{}:{}",
+            gutter,
+            "-->".blue().bold(),
+            file_name,
+            self.span.line,
+            self.span.column
+        );
+        let _ = writeln!(out, "{} {}", gutter, bar);
+        let _ = writeln!(out, "{} {} {}", line_number.blue().bold(), bar, line);
+        let _ = write!(
+            out,
+            "{} {} {}{}",
+            gutter,
+            bar,
+            padding,
+            "^".repeat(width).red().bold()
+        );
+        if let Some(help) = &self.help {
+            let _ = write!(out, "\n{} {} {}", gutter, "= help:".bold(), help);
+        }
+        out
+    }
+}
+
+impl From<&SolisError> for Diagnostic {
+    fn from(error: &SolisError) -> Self {
+        Self {
+            message: error.to_string(),
+            span: error.span(),
+            help: error.help(),
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use crate::*;
 use rustc_hash::FxHashMap;
-use std::{cell::RefCell, collections::hash_map::Entry, rc::Rc};
+use std::{cell::RefCell, rc::Rc};
 
 pub type RcCell<T> = Rc<RefCell<T>>;
 pub type EnvData = RcCell<Environment>;
@@ -13,14 +13,8 @@ pub struct Environment {
 
 impl Environment {
     pub fn new() -> Rc<RefCell<Self>> {
-        let mut globals = FxHashMap::default();
-        globals.insert(
-            String::from("clock"),
-            ValueWrapper::Func(Rc::new(NativeFuncClock {})),
-        );
-
         Rc::new(RefCell::new(Self {
-            values: globals,
+            values: globals(),
             enclosing: None,
         }))
     }
@@ -36,36 +30,58 @@ impl Environment {
         self.values.insert(key, value);
     }
 
-    pub fn get(&self, key: String, line: usize) -> Result<ValueWrapper> {
-        if let Some(value) = self.values.get(&key) {
-            return Ok(value.clone());
-        };
-
-        if let Some(enclosing) = &self.enclosing {
-            let value = enclosing.borrow().get(key, line);
-            return value;
+    pub fn get_at(&self, distance: usize, key: &str, span: Span) -> Result<ValueWrapper> {
+        if distance == 0 {
+            if let Some(value) = self.values.get(key) {
+                return Ok(value.clone());
+            }
+        } else if let Some(enclosing) = &self.enclosing {
+            return enclosing.borrow().get_at(distance - 1, key, span);
         }
 
         Err(SolisError::RuntimeError(
-            line,
+            span,
             format!("Unexpected variable name `{}`.", key),
         ))
     }
 
-    pub fn assign(&mut self, key: String, value: ValueWrapper, line: usize) -> Result<()> {
-        if let Entry::Occupied(mut e) = self.values.entry(key.clone()) {
-            e.insert(value);
-            return Ok(());
+    pub fn get_global(&self, key: &str, span: Span) -> Result<ValueWrapper> {
+        if let Some(enclosing) = &self.enclosing {
+            return enclosing.borrow().get_global(key, span);
         }
 
-        if let Some(enclosing) = &self.enclosing {
-            let value = enclosing.borrow_mut().assign(key, value, line);
-            return value;
+        self.get_at(0, key, span)
+    }
+
+    pub fn assign_at(
+        &mut self,
+        distance: usize,
+        key: &str,
+        value: ValueWrapper,
+        span: Span,
+    ) -> Result<()> {
+        if distance == 0 {
+            if let Some(slot) = self.values.get_mut(key) {
+                *slot = value;
+                return Ok(());
+            }
+        } else if let Some(enclosing) = &self.enclosing {
+            return enclosing
+                .borrow_mut()
+                .assign_at(distance - 1, key, value, span);
         }
 
         Err(SolisError::RuntimeError(
-            line,
+            span,
             format!("Undefined variable `{}`.", key),
         ))
     }
+
+    pub fn assign_global(&mut self, key: &str, value: ValueWrapper, span: Span) -> Result<()> {
+        if let Some(enclosing) = &self.enclosing {
+            return enclosing.borrow_mut().assign_global(key, value, span);
+        }
+
+        self.assign_at(0, key, value, span)
+    }
 }
@@ -1,38 +1,125 @@
 use thiserror::Error;
 
-use crate::{Token, TokenType, ValueWrapper};
+use crate::{Span, Token};
 pub type Result<T> = std::result::Result<T, SolisError>;
 
 #[derive(Error, Debug)]
 pub enum SolisError {
     // Lexer
-    #[error("unrecognizable character `{0}` was found.")]
-    UnrecognizedCharacter(char),
+    #[error("unrecognizable character `{character}` was found.")]
+    UnrecognizedCharacter { character: char, span: Span },
     #[error("an unterminated string was found")]
-    UnterminatedString,
-    #[error("invalid floating-point literal `{0}` was found.")]
-    InvalidNumber(String),
+    UnterminatedString { span: Span },
+    #[error("invalid escape sequence `{escape}` was found.")]
+    InvalidEscape { escape: String, span: Span },
+    #[error("invalid number literal `{lexeme}`: {reason}.")]
+    InvalidNumber {
+        lexeme: String,
+        reason: String,
+        span: Span,
+    },
 
     // Parser
-    #[error("`{0}` missing literal value")]
-    MissingLiteral(TokenType),
-    #[error("[line {} at `{}`] expected {expected}", token.line, token.lexeme)]
+    #[error("`{}` missing literal value", token.ty)]
+    MissingLiteral { token: Token },
+    #[error("expected {expected}")]
     MissingToken { token: Token, expected: String },
 
+    // Resolver
+    #[error("can't read local variable in its own initializer.")]
+    SelfReferencingInitializer { token: Token },
+    #[error("a variable with this name was already declared in this scope.")]
+    DuplicateDeclaration { token: Token },
+    #[error("'{}' statement was used outside a loop.", token.lexeme)]
+    OutsideLoop { token: Token },
+    #[error("'return' statement was used outside a function.")]
+    OutsideFunction { token: Token },
+    #[error("can't return a value from an initializer.")]
+    InitializerReturn { token: Token },
+    #[error("'{}' was used outside a class.", token.lexeme)]
+    OutsideClass { token: Token },
+    #[error("'super' was used in a class with no superclass.")]
+    NoSuperclass { token: Token },
+    #[error("a class can't inherit from itself.")]
+    SelfInheritance { token: Token },
+
     // Interpreter
-    #[error("[line {} at `{}`] invalid assignment target", token.line, token.lexeme)]
+    #[error("invalid assignment target")]
     InvalidAssignmentTarget { token: Token },
-    #[error("[line {0}] {1}")]
-    RuntimeError(usize, String),
-
-    // Loop tools
-    #[error("[line {} at `{}`] 'break' statement was called outside a loop.", location.line, location.lexeme)]
-    Break { location: Token },
-    #[error("[line {} at `{}`] 'continue' statement was called outside a loop.", location.line, location.lexeme)]
-    Continue { location: Token },
-    #[error("[line {} at `{}`] 'return' statement was called outside a block.", location.line, location.lexeme)]
-    Return {
-        location: Token,
-        value: ValueWrapper,
-    },
+    #[error("{1}")]
+    RuntimeError(Span, String),
+
+    // Serialization
+    #[error("invalid syntax tree JSON: {message}")]
+    InvalidJson { message: String },
+}
+
+impl SolisError {
+    /// The source range the error points at.
+    pub fn span(&self) -> Span {
+        use SolisError::*;
+        match self {
+            UnrecognizedCharacter { span, .. }
+            | UnterminatedString { span }
+            | InvalidEscape { span, .. }
+            | InvalidNumber { span, .. }
+            | RuntimeError(span, _) => *span,
+            MissingLiteral { token }
+            | MissingToken { token, .. }
+            | SelfReferencingInitializer { token }
+            | DuplicateDeclaration { token }
+            | OutsideLoop { token }
+            | OutsideFunction { token }
+            | InitializerReturn { token }
+            | OutsideClass { token }
+            | NoSuperclass { token }
+            | SelfInheritance { token }
+            | InvalidAssignmentTarget { token } => token.span,
+            InvalidJson { .. } => Span::default(),
+        }
+    }
+
+    /// Points a runtime error raised without any source location, such as one from a
+    /// native function, at `span`.
+    pub fn or_span(self, span: Span) -> Self {
+        match self {
+            SolisError::RuntimeError(at, message) if at == Span::default() => {
+                SolisError::RuntimeError(span, message)
+            }
+            err => err,
+        }
+    }
+
+    /// An optional hint on how to fix the error, shown below the source snippet.
+    pub fn help(&self) -> Option<String> {
+        use SolisError::*;
+        Some(match self {
+            UnterminatedString { .. } => String::from("add a closing `\"` to end the string"),
+            InvalidEscape { .. } => String::from(
+                "the escapes are `\\n`, `\\t`, `\\r`, `\\0`, `\\\\`, `\\\"`, `\\$` and `\\u{...}`, \
+                 or use a raw string like `r\"...\"`",
+            ),
+            SelfReferencingInitializer { .. } => {
+                String::from("use a different name, or declare the variable before initializing it")
+            }
+            OutsideLoop { token } => format!(
+                "`{}` can only be used inside a `while` or `for` loop",
+                token.lexeme
+            ),
+            OutsideFunction { .. } => {
+                String::from("`return` can only be used inside a function or method")
+            }
+            InitializerReturn { .. } => String::from(
+                "`init` always returns the new instance, so use a bare `return;` instead",
+            ),
+            OutsideClass { token } => {
+                format!("`{}` can only be used inside a method", token.lexeme)
+            }
+            NoSuperclass { .. } => String::from("add a superclass with `class Name < Superclass`"),
+            InvalidAssignmentTarget { .. } => {
+                String::from("only variables and properties can be assigned to")
+            }
+            _ => return None,
+        })
+    }
 }
@@ -0,0 +1,21 @@
+use crate::*;
+
+/// Serializes a program to JSON, keeping every span and literal value, so that tools
+/// outside of Rust can inspect it.
+pub fn ast_to_json(statements: &[Box<Stmt>]) -> Result<String> {
+    serde_json::to_string_pretty(statements).map_err(SolisError::from)
+}
+
+/// Reads a program serialized by [`ast_to_json`], or generated by another tool. It still
+/// needs resolving before it's run.
+pub fn ast_from_json(json: &str) -> Result<Vec<Box<Stmt>>> {
+    serde_json::from_str(json).map_err(SolisError::from)
+}
+
+impl From<serde_json::Error> for SolisError {
+    fn from(err: serde_json::Error) -> Self {
+        SolisError::InvalidJson {
+            message: err.to_string(),
+        }
+    }
+}
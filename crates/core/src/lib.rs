@@ -1,7 +1,17 @@
 pub mod callable;
+pub mod class;
+pub mod diagnostics;
 pub mod environment;
 pub mod errors;
+pub mod json;
+pub mod map;
+pub mod natives;
+pub mod printer;
 pub mod stmts;
 pub mod typings;
+pub mod visit;
 
-pub use crate::{callable::*, environment::*, errors::*, stmts::*, stmts::*, typings::*};
+pub use crate::{
+    callable::*, class::*, diagnostics::*, environment::*, errors::*, json::*, map::*, natives::*,
+    printer::*, stmts::*, typings::*, visit::*,
+};
@@ -0,0 +1,93 @@
+use crate::*;
+use indexmap::IndexMap;
+use num_bigint::BigInt;
+use num_traits::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use rustc_hash::FxHasher;
+use std::{fmt::Display, hash::BuildHasherDefault};
+
+/// The backing store of a map value. Entries are kept in insertion order.
+pub type SolisMap = IndexMap<MapKey, ValueWrapper, BuildHasherDefault<FxHasher>>;
+
+/// A hashable map key. Unlike `==` on values, keys never compare equal across types,
+/// so `m["1"]` and `m[1]` refer to distinct entries. Numbers are the exception: any number
+/// holding an integer is stored as that integer, so `m[1]`, `m[1.0]`, `m[1n]` and `m[1d]`
+/// are the same, and a decimal a float can hold exactly is stored as that float.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MapKey {
+    Str(String),
+    /// The bits of a non-NaN number, with `-0` folded into `0`.
+    Num(u64),
+    Int(i64),
+    /// An integer too large for `Int`.
+    BigInt(BigInt),
+    /// A normalized decimal that isn't an integer or a float.
+    Decimal(Decimal),
+    Bool(bool),
+    Nil,
+}
+
+impl MapKey {
+    pub fn new(value: &ValueWrapper, span: Span) -> Result<Self> {
+        Ok(match value {
+            ValueWrapper::Str(s) => Self::Str(s.clone()),
+            ValueWrapper::Num(n) if n.is_nan() => {
+                return Err(SolisError::RuntimeError(
+                    span,
+                    String::from("NaN can't be used as a map key."),
+                ))
+            }
+            ValueWrapper::Num(n)
+                if n.fract() == 0.0
+                    && let Some(n) = BigInt::from_f64(*n) =>
+            {
+                Self::integer(n)
+            }
+            ValueWrapper::Num(n) => Self::Num(n.to_bits()),
+            ValueWrapper::Int(n) => Self::Int(*n),
+            ValueWrapper::BigInt(n) => Self::integer(n.clone()),
+            ValueWrapper::Decimal(n) if n.is_integer() => {
+                Self::integer(n.to_i128().unwrap().into())
+            }
+            ValueWrapper::Decimal(n) => match n.to_f64() {
+                Some(float) if Decimal::from_f64_retain(float) == Some(*n) => {
+                    Self::Num(float.to_bits())
+                }
+                _ => Self::Decimal(n.normalize()),
+            },
+            ValueWrapper::Bool(b) => Self::Bool(*b),
+            ValueWrapper::Nil => Self::Nil,
+            value => {
+                return Err(SolisError::RuntimeError(
+                    span,
+                    format!("`{}` can't be used as a map key.", value),
+                ))
+            }
+        })
+    }
+
+    fn integer(n: BigInt) -> Self {
+        match n.to_i64() {
+            Some(n) => Self::Int(n),
+            None => Self::BigInt(n),
+        }
+    }
+
+    pub fn to_value(&self) -> ValueWrapper {
+        match self {
+            Self::Str(s) => ValueWrapper::Str(s.clone()),
+            Self::Num(bits) => ValueWrapper::Num(f64::from_bits(*bits)),
+            Self::Int(n) => ValueWrapper::Int(*n),
+            Self::BigInt(n) => ValueWrapper::BigInt(n.clone()),
+            Self::Decimal(n) => ValueWrapper::Decimal(*n),
+            Self::Bool(b) => ValueWrapper::Bool(*b),
+            Self::Nil => ValueWrapper::Nil,
+        }
+    }
+}
+
+impl Display for MapKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.to_value(), f)
+    }
+}
@@ -0,0 +1,161 @@
+use crate::*;
+use num_bigint::BigInt;
+use num_traits::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use rustc_hash::FxHashMap;
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The global functions every backend starts with.
+pub fn globals() -> FxHashMap<String, ValueWrapper> {
+    let natives = [
+        NativeFunction::new("clock", 0, clock),
+        NativeFunction::new("len", 1, len),
+        NativeFunction::new("keys", 1, keys),
+        NativeFunction::new("delete", 2, delete),
+        NativeFunction::new("int", 1, int),
+        NativeFunction::new("float", 1, float),
+        NativeFunction::new("bigint", 1, bigint),
+        NativeFunction::new("decimal", 1, decimal),
+    ];
+
+    natives
+        .into_iter()
+        .map(|native| (native.name().to_string(), ValueWrapper::Func(native)))
+        .collect()
+}
+
+fn clock(_arguments: Arguments) -> Result<ValueWrapper> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("SystemTime before UNIX_EPOCH.");
+    Ok(ValueWrapper::Num(now.as_secs_f64()))
+}
+
+fn len(arguments: Arguments) -> Result<ValueWrapper> {
+    let len = match arguments.get(0)? {
+        ValueWrapper::List(list) => list.borrow().len(),
+        ValueWrapper::Map(map) => map.borrow().len(),
+        ValueWrapper::Str(s) => s.chars().count(),
+        value => {
+            return Err(SolisError::RuntimeError(
+                Span::default(),
+                format!("`len` expects a list, map or string, found `{}`.", value),
+            ))
+        }
+    };
+    Ok(ValueWrapper::Int(len as i64))
+}
+
+fn keys(arguments: Arguments) -> Result<ValueWrapper> {
+    let map: RcCell<SolisMap> = arguments.get(0)?;
+    let keys = map.borrow().keys().map(MapKey::to_value).collect();
+    Ok(ValueWrapper::List(Rc::new(RefCell::new(keys))))
+}
+
+/// Removes a key from a map, returning its value or `nil` if it was absent.
+fn delete(arguments: Arguments) -> Result<ValueWrapper> {
+    let map: RcCell<SolisMap> = arguments.get(0)?;
+    let key = MapKey::new(&arguments.get(1)?, Span::default())?;
+    let value = map.borrow_mut().shift_remove(&key);
+    Ok(value.unwrap_or(ValueWrapper::Nil))
+}
+
+fn cannot_convert(value: &ValueWrapper, to: &str) -> SolisError {
+    SolisError::RuntimeError(
+        Span::default(),
+        format!("`{}` can't be converted to {}.", value, to),
+    )
+}
+
+/// Converts a number or numeric string to a big integer, truncating any fraction.
+fn to_bigint(value: &ValueWrapper) -> Option<BigInt> {
+    match value {
+        ValueWrapper::Num(x) => BigInt::from_f64(x.trunc()),
+        ValueWrapper::Decimal(x) => x.trunc().to_i128().map(BigInt::from),
+        ValueWrapper::Str(s) => parse_truncated(s.trim()),
+        value => value.to_bigint(),
+    }
+}
+
+/// Parses a numeric string like `-12.5e3` exactly, truncating any fraction.
+fn parse_truncated(s: &str) -> Option<BigInt> {
+    let (mantissa, exponent) = match s.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().ok()?),
+        None => (s, 0),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if !fraction.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    // The digits as an integer, scaled back by the fraction and the exponent.
+    let digits: BigInt = format!("{}{}", whole, fraction).parse().ok()?;
+    let shift = exponent.checked_sub(fraction.len() as i64)?;
+    let scale = |shift: u64| Some(BigInt::from(10).pow(u16::try_from(shift).ok()? as u32));
+    match shift {
+        0.. => Some(digits * scale(shift as u64)?),
+        _ => Some(match scale(shift.unsigned_abs()) {
+            Some(scale) => digits / scale,
+            // Shifted past every digit there could be.
+            None => BigInt::from(0),
+        }),
+    }
+}
+
+/// Parses a numeric string, which may have a fraction or an exponent, as a decimal.
+fn parse_decimal(s: &str) -> Option<Decimal> {
+    match s.contains(['e', 'E']) {
+        true => Decimal::from_scientific(s).ok(),
+        false => Decimal::from_str_exact(s).ok(),
+    }
+}
+
+/// Converts a number or numeric string to an integer, truncating any fraction.
+fn int(arguments: Arguments) -> Result<ValueWrapper> {
+    let value = arguments.get(0)?;
+    match to_bigint(&value).and_then(|x| x.to_i64()) {
+        Some(x) => Ok(ValueWrapper::Int(x)),
+        None => Err(cannot_convert(&value, "an integer")),
+    }
+}
+
+/// Converts a number or numeric string to a float, rounding it if needed.
+fn float(arguments: Arguments) -> Result<ValueWrapper> {
+    let value = arguments.get(0)?;
+    let float = match &value {
+        ValueWrapper::Str(s) => s.trim().parse().ok(),
+        value => value.to_float(),
+    };
+    match float {
+        Some(x) => Ok(ValueWrapper::Num(x)),
+        None => Err(cannot_convert(&value, "a float")),
+    }
+}
+
+/// Converts a number or numeric string to a big integer, truncating any fraction.
+fn bigint(arguments: Arguments) -> Result<ValueWrapper> {
+    let value = arguments.get(0)?;
+    match to_bigint(&value) {
+        Some(x) => Ok(ValueWrapper::BigInt(x)),
+        None => Err(cannot_convert(&value, "a big integer")),
+    }
+}
+
+/// Converts a number or numeric string to a decimal. A float becomes the shortest
+/// decimal that rounds to it, so `decimal(0.1)` is `0.1d`.
+fn decimal(arguments: Arguments) -> Result<ValueWrapper> {
+    let value = arguments.get(0)?;
+    let decimal = match &value {
+        ValueWrapper::Num(x) => x.to_string().parse().ok(),
+        ValueWrapper::Str(s) => parse_decimal(s.trim()),
+        value => value.to_decimal(),
+    };
+    match decimal {
+        Some(x) => Ok(ValueWrapper::Decimal(x)),
+        None => Err(cannot_convert(&value, "a decimal")),
+    }
+}
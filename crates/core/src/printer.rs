@@ -0,0 +1,312 @@
+use crate::*;
+use std::rc::Rc;
+
+/// Renders the AST as S-expressions, with every statement on its own indented line, to
+/// show how the parser desugared the source (e.g. `for` loops into blocks and `while`).
+#[derive(Default)]
+pub struct AstPrinter {
+    out: String,
+    depth: usize,
+}
+
+impl AstPrinter {
+    pub fn print(statements: &[Box<Stmt>]) -> String {
+        let mut printer = Self::default();
+        for statement in statements {
+            printer.visit_stmt(statement);
+            printer.out.push('\n');
+        }
+        printer.out
+    }
+
+    fn write(&mut self, text: &str) {
+        self.out.push_str(text);
+    }
+
+    fn newline(&mut self) {
+        self.out.push('\n');
+        self.out.push_str(&"  ".repeat(self.depth));
+    }
+
+    /// Writes `(head expr...`, leaving the node open for the caller to finish.
+    fn open(&mut self, head: &str, exprs: &[&Expr]) {
+        self.write("(");
+        self.write(head);
+        for expr in exprs {
+            self.write(" ");
+            self.visit_expr(expr);
+        }
+    }
+
+    /// Writes `(head expr...)`.
+    fn parenthesize(&mut self, head: &str, exprs: &[&Expr]) {
+        self.open(head, exprs);
+        self.write(")");
+    }
+
+    /// Writes each statement on its own line, one level deeper than the current one.
+    fn nested<'a>(&mut self, statements: impl IntoIterator<Item = &'a Stmt>) {
+        self.depth += 1;
+        for statement in statements {
+            self.newline();
+            self.visit_stmt(statement);
+        }
+        self.depth -= 1;
+    }
+
+    /// Writes the parameter list and body of a function, closing the enclosing node.
+    fn function(&mut self, function: &FunctionDecl) {
+        self.write("(");
+        for (i, param) in function.params.iter().enumerate() {
+            if i > 0 {
+                self.write(" ");
+            }
+            match &param.default {
+                Some(default) => {
+                    self.write("(= ");
+                    self.write(&param.name.lexeme);
+                    self.write(" ");
+                    self.visit_expr(default);
+                    self.write(")");
+                }
+                None => self.write(&param.name.lexeme),
+            }
+        }
+        if let Some(rest) = &function.rest {
+            if !function.params.is_empty() {
+                self.write(" ");
+            }
+            self.write("...");
+            self.write(&rest.lexeme);
+        }
+        self.write(")");
+        self.nested(&function.body);
+        self.write(")");
+    }
+}
+
+impl ExprVisitor for AstPrinter {
+    fn visit_binary(&mut self, left: &Box<Expr>, operator: &Token, right: &Box<Expr>, _: Span) {
+        // Compound assignments desugar into an arithmetic operator keeping the `+=` lexeme.
+        let operator = match operator.ty {
+            TokenType::Plus
+            | TokenType::Minus
+            | TokenType::Star
+            | TokenType::Slash
+            | TokenType::Percent
+            | TokenType::TildeSlash => operator.lexeme.trim_end_matches('='),
+            _ => &operator.lexeme,
+        };
+        self.parenthesize(operator, &[left, right]);
+    }
+
+    fn visit_call(
+        &mut self,
+        callee: &Box<Expr>,
+        _: &Token,
+        args: &Vec<Box<Expr>>,
+        named: &Vec<(Token, Box<Expr>)>,
+        _: Span,
+    ) {
+        let mut exprs: Vec<&Expr> = vec![callee];
+        exprs.extend(args.iter().map(Box::as_ref));
+        self.open("call", &exprs);
+        for (name, value) in named {
+            self.write(" (");
+            self.write(&name.lexeme);
+            self.write(": ");
+            self.visit_expr(value);
+            self.write(")");
+        }
+        self.write(")");
+    }
+
+    fn visit_grouping(&mut self, expression: &Box<Expr>, _: Span) {
+        self.parenthesize("group", &[expression]);
+    }
+
+    fn visit_literal(&mut self, value: &ValueWrapper, _: Span) {
+        self.write(&value.to_string());
+    }
+
+    fn visit_logical(&mut self, left: &Box<Expr>, operator: &Token, right: &Box<Expr>, _: Span) {
+        self.parenthesize(&operator.lexeme, &[left, right]);
+    }
+
+    fn visit_unary(&mut self, operator: &Token, right: &Box<Expr>, _: Span) {
+        self.parenthesize(&operator.lexeme, &[right]);
+    }
+
+    fn visit_variable(&mut self, name: &Token, _: &Option<usize>, _: Span) {
+        self.write(&name.lexeme);
+    }
+
+    fn visit_assign(&mut self, name: &Token, value: &Box<Expr>, _: &Option<usize>, _: Span) {
+        self.parenthesize(&format!("= {}", name.lexeme), &[value]);
+    }
+
+    fn visit_get(&mut self, object: &Box<Expr>, name: &Token, _: Span) {
+        self.open(".", &[object]);
+        self.write(" ");
+        self.write(&name.lexeme);
+        self.write(")");
+    }
+
+    fn visit_set(&mut self, object: &Box<Expr>, name: &Token, value: &Box<Expr>, _: Span) {
+        self.open("= (.", &[object]);
+        self.write(" ");
+        self.write(&name.lexeme);
+        self.write(") ");
+        self.visit_expr(value);
+        self.write(")");
+    }
+
+    fn visit_this(&mut self, _: &Token, _: &Option<usize>, _: Span) {
+        self.write("this");
+    }
+
+    fn visit_super_expr(&mut self, _: &Token, method: &Token, _: &Option<usize>, _: Span) {
+        self.write("(super ");
+        self.write(&method.lexeme);
+        self.write(")");
+    }
+
+    fn visit_list(&mut self, elements: &Vec<Box<Expr>>, _: Span) {
+        let elements: Vec<&Expr> = elements.iter().map(Box::as_ref).collect();
+        self.parenthesize("list", &elements);
+    }
+
+    fn visit_map(&mut self, entries: &Vec<(Box<Expr>, Box<Expr>)>, _: Span) {
+        self.write("(map");
+        for (key, value) in entries {
+            self.write(" ");
+            self.parenthesize("entry", &[key, value]);
+        }
+        self.write(")");
+    }
+
+    fn visit_index(&mut self, object: &Box<Expr>, _: &Token, index: &Box<Expr>, _: Span) {
+        self.parenthesize("index", &[object, index]);
+    }
+
+    fn visit_set_index(
+        &mut self,
+        object: &Box<Expr>,
+        _: &Token,
+        index: &Box<Expr>,
+        value: &Box<Expr>,
+        operator: &Option<Token>,
+        _: Span,
+    ) {
+        let operator = operator.as_ref().map_or("=", |operator| &operator.lexeme);
+        self.open(&format!("{} (index", operator), &[object, index]);
+        self.write(") ");
+        self.visit_expr(value);
+        self.write(")");
+    }
+
+    fn visit_interpolation(&mut self, parts: &Vec<Box<Expr>>, _: Span) {
+        let parts: Vec<&Expr> = parts.iter().map(Box::as_ref).collect();
+        self.parenthesize("interpolate", &parts);
+    }
+
+    fn visit_lambda(&mut self, _: &Token, function: &Rc<FunctionDecl>, _: Span) {
+        self.write("(lambda ");
+        self.function(function);
+    }
+}
+
+impl StmtVisitor for AstPrinter {
+    fn visit_block(&mut self, statements: &Vec<Stmt>, _: Span) {
+        self.write("(block");
+        self.nested(statements);
+        self.write(")");
+    }
+
+    fn visit_expression(&mut self, expression: &Box<Expr>, _: Span) {
+        self.parenthesize("expr", &[expression]);
+    }
+
+    fn visit_break_stmt(&mut self, _: &Token, _: Span) {
+        self.write("(break)");
+    }
+
+    fn visit_continue_stmt(&mut self, _: &Token, _: Span) {
+        self.write("(continue)");
+    }
+
+    fn visit_var(&mut self, name: &Token, inititalizer: &Option<Box<Expr>>, _: Span) {
+        let head = format!("var {}", name.lexeme);
+        match inititalizer {
+            Some(inititalizer) => self.parenthesize(&head, &[inititalizer]),
+            None => self.parenthesize(&head, &[]),
+        }
+    }
+
+    fn visit_print(&mut self, expression: &Box<Expr>, _: Span) {
+        self.parenthesize("print", &[expression]);
+    }
+
+    fn visit_if_stmt(
+        &mut self,
+        condition: &Box<Expr>,
+        then_branch: &Box<Stmt>,
+        else_branch: &Option<Box<Stmt>>,
+        _: Span,
+    ) {
+        self.open("if", &[condition]);
+        self.nested(
+            [then_branch.as_ref()]
+                .into_iter()
+                .chain(else_branch.as_deref()),
+        );
+        self.write(")");
+    }
+
+    fn visit_while_stmt(
+        &mut self,
+        condition: &Box<Expr>,
+        body: &Box<Stmt>,
+        increment: &Option<Box<Expr>>,
+        _: Span,
+    ) {
+        self.open("while", &[condition]);
+        self.nested([body.as_ref()]);
+        if let Some(increment) = increment {
+            self.depth += 1;
+            self.newline();
+            self.parenthesize("increment", &[increment]);
+            self.depth -= 1;
+        }
+        self.write(")");
+    }
+
+    fn visit_function(&mut self, name: &Token, function: &Rc<FunctionDecl>, _: Span) {
+        self.write("(fn ");
+        self.write(&name.lexeme);
+        self.write(" ");
+        self.function(function);
+    }
+
+    fn visit_class(
+        &mut self,
+        name: &Token,
+        superclass: &Option<Box<Expr>>,
+        methods: &Vec<Stmt>,
+        _: Span,
+    ) {
+        self.write("(class ");
+        self.write(&name.lexeme);
+        if let Some(superclass) = superclass {
+            self.write(" < ");
+            self.visit_expr(superclass);
+        }
+        self.nested(methods);
+        self.write(")");
+    }
+
+    fn visit_return_stmt(&mut self, _: &Token, value: &Option<Box<Expr>>, _: Span) {
+        let value: Vec<&Expr> = value.iter().map(AsRef::as_ref).collect();
+        self.parenthesize("return", &value);
+    }
+}
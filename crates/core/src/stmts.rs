@@ -1,14 +1,101 @@
 use crate::*;
+use serde::{Deserialize, Serialize};
 use std::rc::Rc;
 
+/// Defines an AST enum along with a boxed constructor per variant. Every variant also
+/// gets a trailing `span` field recording the source range it was parsed from.
+///
+/// It also generates a `<Enum>Visitor` and `<Enum>VisitorMut` trait, with a method per
+/// variant taking its fields. By default these walk the variant's children and return
+/// [`VisitResult::output`], so a visitor only overrides the variants it cares about.
+/// An enum named after a `:` is one whose nodes can appear inside this one, and its
+/// visitor becomes a supertrait.
 macro_rules! define_ast {
-    ($root_name:ident{$($sub:ident{$($key:ident: $value:ty),*}),+}) => {
+    ($root_name:ident $(: $inner:ident)? {$($sub:ident{$($key:ident: $value:ty),*}),+}) => {
         paste::paste! {
-            #[derive(Clone, Debug)]
-            pub enum $root_name {$($sub {$($key: $value,)*},)*}
+            #[derive(Clone, Debug, Serialize, Deserialize)]
+            pub enum $root_name {$($sub {$($key: $value,)* span: Span},)*}
             impl $root_name {
-                $(pub fn [<$sub:lower>]($($key: $value,)*) -> Box<Self> {
-                    Box::new(Self::$sub {$($key,)*})
+                $(pub fn [<$sub:lower>]($($key: $value,)* span: Span) -> Box<Self> {
+                    Box::new(Self::$sub {$($key,)* span})
+                })*
+
+                pub fn span(&self) -> Span {
+                    match self {
+                        $(Self::$sub { span, .. } => *span,)*
+                    }
+                }
+
+                /// Walks the children of this node with an expression visitor, without
+                /// visiting the node itself.
+                pub fn walk_children<R: VisitResult, V: ExprVisitor<R> + ?Sized>(
+                    &self,
+                    visitor: &mut V,
+                ) -> R {
+                    match self {
+                        $(Self::$sub { $($key,)* .. } => {
+                            $(let result = $key.walk_exprs(visitor);
+                            if result.is_break() {
+                                return result;
+                            })*
+                        })*
+                    }
+                    R::output()
+                }
+
+                pub fn walk_children_mut<R: VisitResult, V: ExprVisitorMut<R> + ?Sized>(
+                    &mut self,
+                    visitor: &mut V,
+                ) -> R {
+                    match self {
+                        $(Self::$sub { $($key,)* .. } => {
+                            $(let result = $key.walk_exprs_mut(visitor);
+                            if result.is_break() {
+                                return result;
+                            })*
+                        })*
+                    }
+                    R::output()
+                }
+            }
+
+            #[allow(clippy::borrowed_box, clippy::ptr_arg)]
+            pub trait [<$root_name Visitor>]<R: VisitResult = ()> $(: [<$inner Visitor>]<R>)? {
+                fn [<visit_ $root_name:lower>](&mut self, node: &$root_name) -> R {
+                    match node {
+                        $($root_name::$sub { $($key,)* span } => {
+                            self.[<visit_ $sub:snake>]($($key,)* *span)
+                        })*
+                    }
+                }
+
+                $(fn [<visit_ $sub:snake>](&mut self, $($key: &$value,)* span: Span) -> R {
+                    let _ = span;
+                    $(let result = $key.[<walk_ $root_name:lower s>](self);
+                    if result.is_break() {
+                        return result;
+                    })*
+                    R::output()
+                })*
+            }
+
+            #[allow(clippy::borrowed_box, clippy::ptr_arg)]
+            pub trait [<$root_name VisitorMut>]<R: VisitResult = ()> $(: [<$inner VisitorMut>]<R>)? {
+                fn [<visit_ $root_name:lower _mut>](&mut self, node: &mut $root_name) -> R {
+                    match node {
+                        $($root_name::$sub { $($key,)* span } => {
+                            self.[<visit_ $sub:snake _mut>]($($key,)* *span)
+                        })*
+                    }
+                }
+
+                $(fn [<visit_ $sub:snake _mut>](&mut self, $($key: &mut $value,)* span: Span) -> R {
+                    let _ = span;
+                    $(let result = $key.[<walk_ $root_name:lower s_mut>](self);
+                    if result.is_break() {
+                        return result;
+                    })*
+                    R::output()
                 })*
             }
         }
@@ -25,7 +112,8 @@ define_ast!(
         Call {
             callee: Box<Expr>,
             paren: Token,
-            args: Vec<Box<Expr>>
+            args: Vec<Box<Expr>>,
+            named: Vec<(Token, Box<Expr>)>
         },
         Grouping {
             expression: Box<Expr>
@@ -43,17 +131,62 @@ define_ast!(
             right: Box<Expr>
         },
         Variable {
-            name: Token
+            name: Token,
+            depth: Option<usize>
         },
         Assign {
+            name: Token,
+            value: Box<Expr>,
+            depth: Option<usize>
+        },
+        Get {
+            object: Box<Expr>,
+            name: Token
+        },
+        Set {
+            object: Box<Expr>,
             name: Token,
             value: Box<Expr>
+        },
+        This {
+            keyword: Token,
+            depth: Option<usize>
+        },
+        SuperExpr {
+            keyword: Token,
+            method: Token,
+            depth: Option<usize>
+        },
+        List {
+            elements: Vec<Box<Expr>>
+        },
+        Map {
+            entries: Vec<(Box<Expr>, Box<Expr>)>
+        },
+        Index {
+            object: Box<Expr>,
+            bracket: Token,
+            index: Box<Expr>
+        },
+        SetIndex {
+            object: Box<Expr>,
+            bracket: Token,
+            index: Box<Expr>,
+            value: Box<Expr>,
+            operator: Option<Token>
+        },
+        Lambda {
+            keyword: Token,
+            function: Rc<FunctionDecl>
+        },
+        Interpolation {
+            parts: Vec<Box<Expr>>
         }
     }
 );
 
 define_ast!(
-    Stmt {
+    Stmt: Expr {
         Block {
             statements: Vec<Stmt>
         },
@@ -80,19 +213,55 @@ define_ast!(
         },
         WhileStmt {
             condition: Box<Expr>,
-            body: Box<Stmt>
+            body: Box<Stmt>,
+            increment: Option<Box<Expr>>
         },
         Function {
-            instance: Rc<dyn Callable>
+            name: Token,
+            function: Rc<FunctionDecl>
+        },
+        Class {
+            name: Token,
+            superclass: Option<Box<Expr>>,
+            methods: Vec<Stmt>
         },
         ReturnStmt {
             keyword: Token,
-            value: Box<Expr>
+            value: Option<Box<Expr>>
         }
     }
 );
 
-/*
-name: Token,
-            params: Vec<Token>,
-            body: Box<Stmt>, */
+/// The parameters and body of a function. Declarations, methods and lambdas all share
+/// it, and each backend builds its own runtime function from it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FunctionDecl {
+    pub params: Vec<Param>,
+    /// Collects the arguments beyond `params` into a list.
+    pub rest: Option<Token>,
+    pub body: Vec<Stmt>,
+}
+
+impl FunctionDecl {
+    /// The number of leading parameters without a default value.
+    pub fn required(&self) -> usize {
+        self.params
+            .iter()
+            .take_while(|param| param.default.is_none())
+            .count()
+    }
+
+    pub fn arity(&self) -> Arity {
+        match self.rest {
+            Some(_) => Arity::at_least(self.required()),
+            None => Arity::range(self.required(), self.params.len()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Param {
+    pub name: Token,
+    /// Evaluated on each call that leaves the parameter out.
+    pub default: Option<Expr>,
+}
@@ -1,33 +1,134 @@
 use crate::*;
+use num_integer::Integer;
+use num_traits::{FromPrimitive, ToPrimitive, Zero};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 use std::{
+    cmp::Ordering,
     fmt::{Debug, Display},
     rc::Rc,
 };
 
-#[derive(Debug, Clone, PartialEq)]
+/// A range of source code. `start` and `end` are byte offsets into the source, while
+/// `line` and `column` (both 1-based, column counted in characters) locate `start`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Self {
+        Self {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+
+    /// Returns a span covering everything from the start of `self` to the end of `other`.
+    pub fn to(self, other: Span) -> Self {
+        Self {
+            end: other.end.max(self.end),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Token {
     pub ty: TokenType,
     pub lexeme: String,
     pub literal: Option<ValueWrapper>,
-    pub line: usize,
+    pub span: Span,
 }
 
-#[derive(Debug, Clone)]
+/// Only the variants a literal can hold are (de)serializable; serializing a runtime
+/// value such as a function fails.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ValueWrapper {
     Str(String),
     Num(f64),
+    Int(i64),
+    /// An integer of any size, written with an `n` suffix like `123n`.
+    #[serde(with = "bigint_string")]
+    BigInt(num_bigint::BigInt),
+    /// A fixed-point decimal, written with a `d` suffix like `19.99d`.
+    Decimal(Decimal),
     Bool(bool),
+    #[serde(skip)]
     Func(Rc<dyn Callable>),
+    #[serde(skip)]
+    Class(Rc<SolisClass>),
+    #[serde(skip)]
+    Instance(RcCell<SolisInstance>),
+    #[serde(skip)]
+    List(RcCell<Vec<ValueWrapper>>),
+    #[serde(skip)]
+    Map(RcCell<SolisMap>),
     Nil,
 }
 
+/// Serializes big integers as decimal strings, as decimals are, rather than as limbs.
+mod bigint_string {
+    use num_bigint::BigInt;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &BigInt, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigInt, D::Error> {
+        let digits = String::deserialize(deserializer)?;
+        digits.parse().map_err(D::Error::custom)
+    }
+}
+
 impl PartialEq for ValueWrapper {
     fn eq(&self, other: &Self) -> bool {
+        self.equals(other, &mut Vec::new())
+    }
+}
+
+impl ValueWrapper {
+    /// Compares two values, where `comparing` holds the pairs of lists and maps being
+    /// compared further up. Meeting one of those pairs again means the values are
+    /// cyclic, and the pair is taken to be equal so the comparison ends.
+    fn equals(&self, other: &Self, comparing: &mut Vec<(*const (), *const ())>) -> bool {
         use ValueWrapper::*;
         match (self, other) {
             (Num(l0), Num(r0)) => l0 == r0,
+            (Int(l0), Int(r0)) => l0 == r0,
+            (Int(_), Num(r0)) => int_cmp_float(self, *r0) == Some(Ordering::Equal),
+            (Num(l0), Int(_)) => int_cmp_float(other, *l0) == Some(Ordering::Equal),
             (Str(l0), Str(r0)) => l0 == r0,
             (Bool(l0), Bool(r0)) => l0 == r0,
+            (Class(l0), Class(r0)) => Rc::ptr_eq(l0, r0),
+            (Instance(l0), Instance(r0)) => Rc::ptr_eq(l0, r0),
+            (List(l0), List(r0)) => {
+                Rc::ptr_eq(l0, r0)
+                    || Self::nested(l0, r0, comparing, |comparing| {
+                        let (l0, r0) = (l0.borrow(), r0.borrow());
+                        l0.len() == r0.len()
+                            && l0
+                                .iter()
+                                .zip(r0.iter())
+                                .all(|(l, r)| l.equals(r, comparing))
+                    })
+            }
+            (Map(l0), Map(r0)) => {
+                Rc::ptr_eq(l0, r0)
+                    || Self::nested(l0, r0, comparing, |comparing| {
+                        let (l0, r0) = (l0.borrow(), r0.borrow());
+                        l0.len() == r0.len()
+                            && l0
+                                .iter()
+                                .all(|(key, l)| r0.get(key).is_some_and(|r| l.equals(r, comparing)))
+                    })
+            }
 
             (Nil, Nil) => true,
             (Nil, _) => false,
@@ -36,14 +137,93 @@ impl PartialEq for ValueWrapper {
             (Str(l0), Num(l1)) => l0 == &l1.to_string(),
             (Num(l0), Str(l1)) => &l0.to_string() == l1,
 
+            (Str(l0), Int(l1)) | (Int(l1), Str(l0)) => l0 == &l1.to_string(),
+
             (Num(l0), Bool(l1)) => l0 == &(*l1 as u8 as f64),
             (Bool(l0), Num(l1)) => &(*l0 as u8 as f64) == l1,
+            (Int(l0), Bool(l1)) | (Bool(l1), Int(l0)) => *l0 == *l1 as i64,
+
+            // The exact number types are equal when they hold the same number, and a
+            // float only equals one if it converts to it exactly.
+            (Int(_) | BigInt(_), Int(_) | BigInt(_)) => self.to_bigint() == other.to_bigint(),
+            (Decimal(_), Int(_) | BigInt(_) | Decimal(_)) | (Int(_) | BigInt(_), Decimal(_)) => {
+                self.to_decimal()
+                    .is_some_and(|l| other.to_decimal() == Some(l))
+            }
+            (BigInt(l0), Num(r0)) | (Num(r0), BigInt(l0)) => {
+                r0.fract() == 0.0 && num_bigint::BigInt::from_f64(*r0).as_ref() == Some(l0)
+            }
+            (Decimal(l0), Num(r0)) | (Num(r0), Decimal(l0)) => {
+                rust_decimal::Decimal::from_f64_retain(*r0) == Some(*l0)
+            }
 
             _ => false,
             // (Bool(l0), Str(l1)) => &l0.to_string() == l1,
             // (Str(l0), Bool(l1)) => l0 == &l1.to_string(),
         }
     }
+
+    /// Runs `compare` on two containers unless they're already being compared.
+    fn nested<T, U>(
+        left: &RcCell<T>,
+        right: &RcCell<U>,
+        comparing: &mut Vec<(*const (), *const ())>,
+        compare: impl FnOnce(&mut Vec<(*const (), *const ())>) -> bool,
+    ) -> bool {
+        let pair = (Rc::as_ptr(left).cast(), Rc::as_ptr(right).cast());
+        if comparing.contains(&pair) {
+            return true;
+        }
+        comparing.push(pair);
+        let equal = compare(comparing);
+        comparing.pop();
+        equal
+    }
+
+    /// Writes the value, where `writing` holds the lists and maps it's nested in. A
+    /// container that holds itself is written as `[...]` or `{...}` where it recurs.
+    fn write(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        writing: &mut Vec<*const ()>,
+    ) -> std::fmt::Result {
+        match self {
+            ValueWrapper::List(list) => {
+                let pointer = Rc::as_ptr(list).cast();
+                if writing.contains(&pointer) {
+                    return f.write_str("[...]");
+                }
+                writing.push(pointer);
+                f.write_str("[")?;
+                for (i, item) in list.borrow().iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    item.write(f, writing)?;
+                }
+                writing.pop();
+                f.write_str("]")
+            }
+            ValueWrapper::Map(map) => {
+                let pointer = Rc::as_ptr(map).cast();
+                if writing.contains(&pointer) {
+                    return f.write_str("{...}");
+                }
+                writing.push(pointer);
+                f.write_str("{")?;
+                for (i, (key, value)) in map.borrow().iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    f.write_fmt(format_args!("{}: ", key))?;
+                    value.write(f, writing)?;
+                }
+                writing.pop();
+                f.write_str("}")
+            }
+            value => Display::fmt(value, f),
+        }
+    }
 }
 
 impl From<f64> for ValueWrapper {
@@ -52,12 +232,36 @@ impl From<f64> for ValueWrapper {
     }
 }
 
+impl From<i64> for ValueWrapper {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<num_bigint::BigInt> for ValueWrapper {
+    fn from(value: num_bigint::BigInt) -> Self {
+        Self::BigInt(value)
+    }
+}
+
+impl From<Decimal> for ValueWrapper {
+    fn from(value: Decimal) -> Self {
+        Self::Decimal(value)
+    }
+}
+
 impl From<String> for ValueWrapper {
     fn from(value: String) -> Self {
         Self::Str(value)
     }
 }
 
+impl From<&str> for ValueWrapper {
+    fn from(value: &str) -> Self {
+        Self::Str(value.to_string())
+    }
+}
+
 impl From<bool> for ValueWrapper {
     fn from(value: bool) -> Self {
         Self::Bool(value)
@@ -67,24 +271,440 @@ impl From<bool> for ValueWrapper {
 impl ValueWrapper {
     pub fn try_into_num(&self) -> Self {
         match self {
-            Self::Bool(x) => Self::Num(*x as u8 as f64),
+            Self::Bool(x) => Self::Int(*x as i64),
             x => x.clone(),
         }
     }
+
+    /// The text a value adds to a string, as in `"a" + 1` or an interpolation.
+    pub fn stringify(&self) -> String {
+        match self {
+            Self::Str(s) => s.clone(),
+            Self::Num(x) => x.to_string(),
+            Self::BigInt(x) => x.to_string(),
+            Self::Decimal(x) => x.to_string(),
+            value => value.to_string(),
+        }
+    }
+
+    /// Converts a number to a float, rounding it if it can't be represented exactly.
+    pub fn to_float(&self) -> Option<f64> {
+        match self {
+            Self::Num(x) => Some(*x),
+            Self::Int(x) => Some(*x as f64),
+            Self::BigInt(x) => x.to_f64(),
+            Self::Decimal(x) => x.to_f64(),
+            _ => None,
+        }
+    }
+
+    /// Converts an integer of either size to a big integer.
+    pub fn to_bigint(&self) -> Option<num_bigint::BigInt> {
+        match self {
+            Self::Int(x) => Some((*x).into()),
+            Self::BigInt(x) => Some(x.clone()),
+            _ => None,
+        }
+    }
+
+    /// Converts a decimal, or an integer small enough to be one, to a decimal.
+    pub fn to_decimal(&self) -> Option<Decimal> {
+        match self {
+            Self::Int(x) => Some((*x).into()),
+            Self::BigInt(x) => x.to_i128().and_then(Decimal::from_i128),
+            Self::Decimal(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    /// Applies a binary `operator` to both operands, returning `None` if the operator
+    /// is not defined for them. Shared by every backend so they agree on semantics.
+    ///
+    /// Arithmetic on two integers stays exact, failing on overflow, except for `/`,
+    /// which always divides as floats. Mixing an integer with a float converts the
+    /// integer to a float, though comparisons between them are exact, agreeing with
+    /// `==`. `~/` and `%` round the quotient down, so the remainder takes
+    /// the sign of the divisor.
+    ///
+    /// Mixing an integer with a big integer gives a big integer, and mixing either with
+    /// a decimal gives a decimal. Decimals keep 28 significant digits, so `/` rounds a
+    /// quotient like `1d / 3d` to that many. A decimal can't be mixed with a float,
+    /// since that would silently lose the exactness.
+    pub fn binary(&self, operator: &TokenType, right: &Self) -> Result<Option<Self>> {
+        use {TokenType::*, ValueWrapper::*};
+
+        match operator {
+            BangEqual => return Ok(Some((!(self == right)).into())),
+            EqualEqual => return Ok(Some((self == right).into())),
+            _ => (),
+        }
+
+        let (left, right) = (self.try_into_num(), right.try_into_num());
+        let expression = || format!("{} {} {}", left, operator.symbol(), right);
+        Ok(match (&left, &right) {
+            (Int(l), Int(r)) => return int_binary(*l, operator, *r),
+            (Int(_) | BigInt(_), Int(_) | BigInt(_))
+                if let (Some(l), Some(r)) = (left.to_bigint(), right.to_bigint()) =>
+            {
+                return bigint_binary(&l, operator, &r)
+            }
+            (Decimal(_), Num(_)) | (Num(_), Decimal(_)) => {
+                return Err(SolisError::RuntimeError(
+                    Span::default(),
+                    format!(
+                        "Can't mix a decimal with a float in `{}`, convert one of them with `decimal` or `float` first.",
+                        expression()
+                    ),
+                ))
+            }
+            (Decimal(_), Int(_) | BigInt(_) | Decimal(_)) | (Int(_) | BigInt(_), Decimal(_)) => {
+                return match (left.to_decimal(), right.to_decimal()) {
+                    (Some(l), Some(r)) => decimal_binary(l, operator, r, expression),
+                    _ => Err(decimal_overflow(expression())),
+                }
+            }
+            (Int(_) | BigInt(_), Num(r)) if is_comparison(operator) => {
+                compare(int_cmp_float(&left, *r), operator)
+            }
+            (Num(l), Int(_) | BigInt(_)) if is_comparison(operator) => {
+                compare(int_cmp_float(&right, *l).map(Ordering::reverse), operator)
+            }
+            (Num(_), Num(_) | Int(_) | BigInt(_)) | (Int(_) | BigInt(_), Num(_))
+                if let (Some(l), Some(r)) = (left.to_float(), right.to_float()) =>
+            {
+                float_binary(l, operator, r)
+            }
+            (Str(l), Str(r)) if operator == &Plus => Some((l.clone() + r).into()),
+            (Str(_), number) | (number, Str(_))
+                if operator == &Plus && number.to_float().is_some() =>
+            {
+                Some((left.stringify() + &right.stringify()).into())
+            }
+            _ => None,
+        })
+    }
+
+    /// Reads `self[index]`. Negative list indices count back from the end, and missing
+    /// map keys read as `nil`.
+    pub fn get_index(&self, index: &Self, span: Span) -> Result<Self> {
+        match self {
+            Self::List(list) => {
+                let list = list.borrow();
+                let position = Self::list_position(index, list.len(), span)?;
+                Ok(list[position].clone())
+            }
+            Self::Map(map) => {
+                let key = MapKey::new(index, span)?;
+                Ok(map.borrow().get(&key).cloned().unwrap_or(Self::Nil))
+            }
+            _ => Err(SolisError::RuntimeError(
+                span,
+                String::from("Only lists and maps can be indexed."),
+            )),
+        }
+    }
+
+    /// Performs `self[index] = value`. Negative list indices count back from the end,
+    /// and missing map keys are inserted.
+    pub fn set_index(&self, index: &Self, value: Self, span: Span) -> Result<()> {
+        match self {
+            Self::List(list) => {
+                let mut list = list.borrow_mut();
+                let position = Self::list_position(index, list.len(), span)?;
+                list[position] = value;
+            }
+            Self::Map(map) => {
+                let key = MapKey::new(index, span)?;
+                map.borrow_mut().insert(key, value);
+            }
+            _ => {
+                return Err(SolisError::RuntimeError(
+                    span,
+                    String::from("Only lists and maps can be indexed."),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    fn list_position(index: &Self, len: usize, span: Span) -> Result<usize> {
+        let index = match index {
+            Self::Int(index) => *index,
+            Self::BigInt(index) if let Some(index) = index.to_i64() => index,
+            Self::Num(index) if index.fract() == 0.0 => *index as i64,
+            index => {
+                return Err(SolisError::RuntimeError(
+                    span,
+                    format!("List index must be an integer, found `{}`.", index),
+                ))
+            }
+        };
+
+        let position = if index < 0 { index + len as i64 } else { index };
+        if position < 0 || position >= len as i64 {
+            return Err(SolisError::RuntimeError(
+                span,
+                format!(
+                    "Index {} is out of bounds for a list of length {}.",
+                    index, len
+                ),
+            ));
+        }
+        Ok(position as usize)
+    }
+
+    /// Applies a unary `operator`, returning `None` if it is not defined for this value.
+    pub fn unary(&self, operator: &TokenType) -> Result<Option<Self>> {
+        use ValueWrapper::*;
+
+        Ok(match operator {
+            TokenType::Minus if let Num(x) = self => Some((-x).into()),
+            TokenType::Minus if let Int(x) = self => match x.checked_neg() {
+                Some(x) => Some(x.into()),
+                None => return Err(overflow(format!("-({})", x))),
+            },
+            TokenType::Minus if let BigInt(x) = self => Some((-x).into()),
+            TokenType::Minus if let Decimal(x) = self => Some((-x).into()),
+            TokenType::Bang => Some(
+                match self {
+                    Bool(x) => !x,
+                    Nil => false,
+                    _ => true,
+                }
+                .into(),
+            ),
+            _ => None,
+        })
+    }
+}
+
+fn is_comparison(operator: &TokenType) -> bool {
+    use TokenType::*;
+    matches!(operator, Less | LessEqual | Greater | GreaterEqual)
+}
+
+/// Applies a comparison `operator` to how its operands are ordered, where `None` means
+/// they're unordered, like anything compared with NaN.
+fn compare(ordering: Option<Ordering>, operator: &TokenType) -> Option<ValueWrapper> {
+    use TokenType::*;
+
+    let Some(ordering) = ordering else {
+        return Some(false.into());
+    };
+    Some(
+        match operator {
+            Less => ordering.is_lt(),
+            LessEqual => ordering.is_le(),
+            Greater => ordering.is_gt(),
+            GreaterEqual => ordering.is_ge(),
+            _ => return None,
+        }
+        .into(),
+    )
+}
+
+/// Orders an integer of either size against a float without rounding the integer.
+fn int_cmp_float(int: &ValueWrapper, float: f64) -> Option<Ordering> {
+    // Integers this small convert to floats exactly.
+    const EXACT: i64 = 1 << f64::MANTISSA_DIGITS;
+    match int {
+        ValueWrapper::Int(int) if (-EXACT..=EXACT).contains(int) => {
+            return (*int as f64).partial_cmp(&float)
+        }
+        _ => (),
+    }
+
+    if float.is_nan() {
+        return None;
+    }
+    if float.is_infinite() {
+        return Some(match float > 0.0 {
+            true => Ordering::Less,
+            false => Ordering::Greater,
+        });
+    }
+    // The float lies between its floor and the next integer, so an integer equal to
+    // the floor is smaller unless the float has no fraction.
+    let floor = num_bigint::BigInt::from_f64(float.floor())?;
+    let tie = match float.fract() {
+        0.0 => Ordering::Equal,
+        _ => Ordering::Less,
+    };
+    Some(int.to_bigint()?.cmp(&floor).then(tie))
+}
+
+fn overflow(expression: String) -> SolisError {
+    SolisError::RuntimeError(
+        Span::default(),
+        format!(
+            "Integer overflow in `{}`, use big integers like `1n` for larger numbers.",
+            expression
+        ),
+    )
+}
+
+fn decimal_overflow(expression: String) -> SolisError {
+    SolisError::RuntimeError(
+        Span::default(),
+        format!("Decimal overflow in `{}`.", expression),
+    )
+}
+
+fn division_by_zero() -> SolisError {
+    SolisError::RuntimeError(Span::default(), String::from("Integer division by zero."))
+}
+
+fn int_binary(l: i64, operator: &TokenType, r: i64) -> Result<Option<ValueWrapper>> {
+    use TokenType::*;
+
+    if matches!(operator, TildeSlash | Percent) && r == 0 {
+        return Err(division_by_zero());
+    }
+    let result = match operator {
+        Greater => return Ok(Some((l > r).into())),
+        GreaterEqual => return Ok(Some((l >= r).into())),
+        Less => return Ok(Some((l < r).into())),
+        LessEqual => return Ok(Some((l <= r).into())),
+        Slash => return Ok(Some((l as f64 / r as f64).into())),
+
+        Plus => l.checked_add(r),
+        Minus => l.checked_sub(r),
+        Star => l.checked_mul(r),
+        TildeSlash => l.checked_div(r).map(|quotient| match l % r {
+            remainder if remainder != 0 && (remainder < 0) != (r < 0) => quotient - 1,
+            _ => quotient,
+        }),
+        Percent => Some(match l.wrapping_rem(r) {
+            remainder if remainder != 0 && (remainder < 0) != (r < 0) => remainder + r,
+            remainder => remainder,
+        }),
+        _ => return Ok(None),
+    };
+    match result {
+        Some(result) => Ok(Some(result.into())),
+        None => Err(overflow(format!("{} {} {}", l, operator.symbol(), r))),
+    }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+fn bigint_binary(
+    l: &num_bigint::BigInt,
+    operator: &TokenType,
+    r: &num_bigint::BigInt,
+) -> Result<Option<ValueWrapper>> {
+    use TokenType::*;
+
+    if matches!(operator, TildeSlash | Percent) && r.is_zero() {
+        return Err(division_by_zero());
+    }
+    Ok(Some(match operator {
+        Greater => (l > r).into(),
+        GreaterEqual => (l >= r).into(),
+        Less => (l < r).into(),
+        LessEqual => (l <= r).into(),
+        Slash => {
+            return Ok(float_binary(
+                l.to_f64().unwrap(),
+                operator,
+                r.to_f64().unwrap(),
+            ))
+        }
+
+        Plus => (l + r).into(),
+        Minus => (l - r).into(),
+        Star => (l * r).into(),
+        TildeSlash => l.div_floor(r).into(),
+        Percent => l.mod_floor(r).into(),
+        _ => return Ok(None),
+    }))
+}
+
+/// Applies `operator` to two decimals, where `expression` describes the operation as
+/// written for the error if the result overflows.
+fn decimal_binary(
+    l: Decimal,
+    operator: &TokenType,
+    r: Decimal,
+    expression: impl FnOnce() -> String,
+) -> Result<Option<ValueWrapper>> {
+    use TokenType::*;
+
+    if matches!(operator, Slash | TildeSlash | Percent) && r.is_zero() {
+        return Err(SolisError::RuntimeError(
+            Span::default(),
+            String::from("Decimal division by zero."),
+        ));
+    }
+    // The remainder of truncating division, moved to the sign of the divisor.
+    let floored_remainder = || {
+        l.checked_rem(r).map(|remainder| {
+            if !remainder.is_zero() && remainder.is_sign_negative() != r.is_sign_negative() {
+                remainder + r
+            } else {
+                remainder
+            }
+        })
+    };
+    let result = match operator {
+        Greater => return Ok(Some((l > r).into())),
+        GreaterEqual => return Ok(Some((l >= r).into())),
+        Less => return Ok(Some((l < r).into())),
+        LessEqual => return Ok(Some((l <= r).into())),
+
+        Plus => l.checked_add(r),
+        Minus => l.checked_sub(r),
+        Star => l.checked_mul(r),
+        Slash => l.checked_div(r),
+        // Dividing out the remainder first keeps the quotient from being rounded up.
+        TildeSlash => floored_remainder().and_then(|remainder| (l - remainder).checked_div(r)),
+        Percent => floored_remainder(),
+        _ => return Ok(None),
+    };
+    match result {
+        Some(result) => Ok(Some(result.into())),
+        None => Err(decimal_overflow(expression())),
+    }
+}
+
+fn float_binary(l: f64, operator: &TokenType, r: f64) -> Option<ValueWrapper> {
+    use TokenType::*;
+
+    Some(match operator {
+        Greater => (l > r).into(),
+        GreaterEqual => (l >= r).into(),
+        Less => (l < r).into(),
+        LessEqual => (l <= r).into(),
+
+        Minus => (l - r).into(),
+        Star => (l * r).into(),
+        Slash => (l / r).into(),
+        Plus => (l + r).into(),
+        TildeSlash => (l / r).floor().into(),
+        Percent => match l % r {
+            remainder if remainder != 0.0 && (remainder < 0.0) != (r < 0.0) => remainder + r,
+            remainder => remainder,
+        }
+        .into(),
+        _ => return None,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TokenType {
     LeftParen,
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
+    Colon,
     Dot,
+    Ellipsis,
     Minus,
     Plus,
     Semicolon,
     Star,
+    Pipe,
 
     BangEqual,
     Bang,
@@ -99,10 +719,19 @@ pub enum TokenType {
     MinusEqual,
     SlashEqual,
     StarEqual,
+    PercentEqual,
+    TildeSlashEqual,
 
     Slash,
+    Percent,
+    /// `~/`, which divides and rounds down.
+    TildeSlash,
     NilLiteral,
     StringLiteral,
+    /// The text of a string literal up to a `${`, or between a `}` and the next `${`.
+    Interpolation,
+    /// The text of a string literal after the `}` of its last interpolated expression.
+    InterpolationEnd,
     NumberLiteral,
     BoolLiteral,
     Identifier,
@@ -126,12 +755,12 @@ pub enum TokenType {
 }
 
 impl Token {
-    pub fn new(ty: TokenType, lexeme: String, literal: Option<ValueWrapper>, line: usize) -> Self {
+    pub fn new(ty: TokenType, lexeme: String, literal: Option<ValueWrapper>, span: Span) -> Self {
         Self {
             ty,
             lexeme,
             literal,
-            line,
+            span,
         }
     }
 }
@@ -142,6 +771,72 @@ impl Display for Token {
     }
 }
 
+/// Source text the parser skips over, kept by lossless lexing so that the source can be
+/// rebuilt from the tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TriviaKind {
+    /// Spaces, tabs and carriage returns.
+    Whitespace,
+    /// A `\n`, or a `\r\n`.
+    Newline,
+    LineComment,
+    BlockComment,
+    /// Text that failed to lex, such as an unrecognized character.
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+    pub span: Span,
+}
+
+/// A token along with the trivia around it. Trailing trivia runs up to the end of the
+/// token's line, while everything from there on leads the next token.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LosslessToken {
+    pub leading: Vec<Trivia>,
+    pub token: Token,
+    pub trailing: Vec<Trivia>,
+}
+
+/// Writes the token exactly as it appeared in the source, trivia included.
+impl Display for LosslessToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for trivia in &self.leading {
+            f.write_str(&trivia.text)?;
+        }
+        f.write_str(&self.token.lexeme)?;
+        for trivia in &self.trailing {
+            f.write_str(&trivia.text)?;
+        }
+        Ok(())
+    }
+}
+
+impl TokenType {
+    /// How an operator is written, for error messages.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            TokenType::EqualEqual => "==",
+            TokenType::BangEqual => "!=",
+            TokenType::Greater => ">",
+            TokenType::GreaterEqual => ">=",
+            TokenType::Less => "<",
+            TokenType::LessEqual => "<=",
+            TokenType::Plus => "+",
+            TokenType::Minus => "-",
+            TokenType::Star => "*",
+            TokenType::Slash => "/",
+            TokenType::Percent => "%",
+            TokenType::TildeSlash => "~/",
+            TokenType::Bang => "!",
+            _ => "?",
+        }
+    }
+}
+
 impl Display for TokenType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!("{:?}", self))
@@ -153,9 +848,17 @@ impl Display for ValueWrapper {
         match self {
             ValueWrapper::Str(s) => f.write_fmt(format_args!("{:?}", s)),
             ValueWrapper::Num(s) => f.write_fmt(format_args!("{:?}", s)),
+            ValueWrapper::Int(s) => f.write_fmt(format_args!("{}", s)),
+            ValueWrapper::BigInt(s) => f.write_fmt(format_args!("{}n", s)),
+            ValueWrapper::Decimal(s) => f.write_fmt(format_args!("{}d", s)),
             ValueWrapper::Bool(s) => f.write_fmt(format_args!("{:?}", s)),
             ValueWrapper::Nil => f.write_str("nil"),
             ValueWrapper::Func(_) => f.write_str("[<Func>]"),
+            ValueWrapper::Class(class) => f.write_str(&class.name),
+            ValueWrapper::Instance(instance) => {
+                f.write_fmt(format_args!("{} instance", instance.borrow().class.name))
+            }
+            ValueWrapper::List(_) | ValueWrapper::Map(_) => self.write(f, &mut Vec::new()),
         }
     }
 }
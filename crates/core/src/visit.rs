@@ -0,0 +1,314 @@
+use crate::*;
+use std::rc::Rc;
+
+/// The result of visiting a node. Default walking stops at the first child whose result
+/// breaks, so a visitor returning `Result<()>` bails out on the first error.
+pub trait VisitResult {
+    /// The result of a node with nothing left to report.
+    fn output() -> Self;
+    fn is_break(&self) -> bool;
+}
+
+impl VisitResult for () {
+    fn output() -> Self {}
+
+    fn is_break(&self) -> bool {
+        false
+    }
+}
+
+impl<E> VisitResult for std::result::Result<(), E> {
+    fn output() -> Self {
+        Ok(())
+    }
+
+    fn is_break(&self) -> bool {
+        self.is_err()
+    }
+}
+
+/// Hands every AST node inside a field to a visitor. This is how the default visitor
+/// methods recurse into the fields of a variant.
+pub trait Walk {
+    fn walk_exprs<R: VisitResult, V: ExprVisitor<R> + ?Sized>(&self, visitor: &mut V) -> R;
+    fn walk_exprs_mut<R: VisitResult, V: ExprVisitorMut<R> + ?Sized>(
+        &mut self,
+        visitor: &mut V,
+    ) -> R;
+    fn walk_stmts<R: VisitResult, V: StmtVisitor<R> + ?Sized>(&self, visitor: &mut V) -> R;
+    fn walk_stmts_mut<R: VisitResult, V: StmtVisitorMut<R> + ?Sized>(
+        &mut self,
+        visitor: &mut V,
+    ) -> R;
+}
+
+impl Walk for Expr {
+    fn walk_exprs<R: VisitResult, V: ExprVisitor<R> + ?Sized>(&self, visitor: &mut V) -> R {
+        visitor.visit_expr(self)
+    }
+
+    fn walk_exprs_mut<R: VisitResult, V: ExprVisitorMut<R> + ?Sized>(
+        &mut self,
+        visitor: &mut V,
+    ) -> R {
+        visitor.visit_expr_mut(self)
+    }
+
+    fn walk_stmts<R: VisitResult, V: StmtVisitor<R> + ?Sized>(&self, visitor: &mut V) -> R {
+        visitor.visit_expr(self)
+    }
+
+    fn walk_stmts_mut<R: VisitResult, V: StmtVisitorMut<R> + ?Sized>(
+        &mut self,
+        visitor: &mut V,
+    ) -> R {
+        visitor.visit_expr_mut(self)
+    }
+}
+
+/// An expression visitor knows nothing about statements, so it walks straight through
+/// them (e.g. in a lambda body) to the expressions inside.
+impl Walk for Stmt {
+    fn walk_exprs<R: VisitResult, V: ExprVisitor<R> + ?Sized>(&self, visitor: &mut V) -> R {
+        self.walk_children(visitor)
+    }
+
+    fn walk_exprs_mut<R: VisitResult, V: ExprVisitorMut<R> + ?Sized>(
+        &mut self,
+        visitor: &mut V,
+    ) -> R {
+        self.walk_children_mut(visitor)
+    }
+
+    fn walk_stmts<R: VisitResult, V: StmtVisitor<R> + ?Sized>(&self, visitor: &mut V) -> R {
+        visitor.visit_stmt(self)
+    }
+
+    fn walk_stmts_mut<R: VisitResult, V: StmtVisitorMut<R> + ?Sized>(
+        &mut self,
+        visitor: &mut V,
+    ) -> R {
+        visitor.visit_stmt_mut(self)
+    }
+}
+
+/// Implements [`Walk`] by walking every item yielded by `$iter`.
+macro_rules! walk_each {
+    ($ty:ty, $($generics:ident),*; $slf:ident => $iter:expr, $iter_mut:expr) => {
+        impl<$($generics: Walk),*> Walk for $ty {
+            fn walk_exprs<R: VisitResult, V: ExprVisitor<R> + ?Sized>(&self, visitor: &mut V) -> R {
+                let $slf = self;
+                for item in $iter {
+                    let result = item.walk_exprs(visitor);
+                    if result.is_break() {
+                        return result;
+                    }
+                }
+                R::output()
+            }
+
+            fn walk_exprs_mut<R: VisitResult, V: ExprVisitorMut<R> + ?Sized>(
+                &mut self,
+                visitor: &mut V,
+            ) -> R {
+                let $slf = self;
+                for item in $iter_mut {
+                    let result = item.walk_exprs_mut(visitor);
+                    if result.is_break() {
+                        return result;
+                    }
+                }
+                R::output()
+            }
+
+            fn walk_stmts<R: VisitResult, V: StmtVisitor<R> + ?Sized>(&self, visitor: &mut V) -> R {
+                let $slf = self;
+                for item in $iter {
+                    let result = item.walk_stmts(visitor);
+                    if result.is_break() {
+                        return result;
+                    }
+                }
+                R::output()
+            }
+
+            fn walk_stmts_mut<R: VisitResult, V: StmtVisitorMut<R> + ?Sized>(
+                &mut self,
+                visitor: &mut V,
+            ) -> R {
+                let $slf = self;
+                for item in $iter_mut {
+                    let result = item.walk_stmts_mut(visitor);
+                    if result.is_break() {
+                        return result;
+                    }
+                }
+                R::output()
+            }
+        }
+    };
+}
+
+walk_each!(Box<T>, T; node => [node.as_ref()], [node.as_mut()]);
+walk_each!(Vec<T>, T; node => node.iter(), node.iter_mut());
+walk_each!(Option<T>, T; node => node.iter(), node.iter_mut());
+
+impl<A: Walk, B: Walk> Walk for (A, B) {
+    fn walk_exprs<R: VisitResult, V: ExprVisitor<R> + ?Sized>(&self, visitor: &mut V) -> R {
+        let result = self.0.walk_exprs(visitor);
+        if result.is_break() {
+            return result;
+        }
+        self.1.walk_exprs(visitor)
+    }
+
+    fn walk_exprs_mut<R: VisitResult, V: ExprVisitorMut<R> + ?Sized>(
+        &mut self,
+        visitor: &mut V,
+    ) -> R {
+        let result = self.0.walk_exprs_mut(visitor);
+        if result.is_break() {
+            return result;
+        }
+        self.1.walk_exprs_mut(visitor)
+    }
+
+    fn walk_stmts<R: VisitResult, V: StmtVisitor<R> + ?Sized>(&self, visitor: &mut V) -> R {
+        let result = self.0.walk_stmts(visitor);
+        if result.is_break() {
+            return result;
+        }
+        self.1.walk_stmts(visitor)
+    }
+
+    fn walk_stmts_mut<R: VisitResult, V: StmtVisitorMut<R> + ?Sized>(
+        &mut self,
+        visitor: &mut V,
+    ) -> R {
+        let result = self.0.walk_stmts_mut(visitor);
+        if result.is_break() {
+            return result;
+        }
+        self.1.walk_stmts_mut(visitor)
+    }
+}
+
+/// Shared function bodies are copied on write, like the resolver already does.
+impl<T: Walk + Clone> Walk for Rc<T> {
+    fn walk_exprs<R: VisitResult, V: ExprVisitor<R> + ?Sized>(&self, visitor: &mut V) -> R {
+        self.as_ref().walk_exprs(visitor)
+    }
+
+    fn walk_exprs_mut<R: VisitResult, V: ExprVisitorMut<R> + ?Sized>(
+        &mut self,
+        visitor: &mut V,
+    ) -> R {
+        Rc::make_mut(self).walk_exprs_mut(visitor)
+    }
+
+    fn walk_stmts<R: VisitResult, V: StmtVisitor<R> + ?Sized>(&self, visitor: &mut V) -> R {
+        self.as_ref().walk_stmts(visitor)
+    }
+
+    fn walk_stmts_mut<R: VisitResult, V: StmtVisitorMut<R> + ?Sized>(
+        &mut self,
+        visitor: &mut V,
+    ) -> R {
+        Rc::make_mut(self).walk_stmts_mut(visitor)
+    }
+}
+
+impl Walk for FunctionDecl {
+    fn walk_exprs<R: VisitResult, V: ExprVisitor<R> + ?Sized>(&self, visitor: &mut V) -> R {
+        let result = self.params.walk_exprs(visitor);
+        if result.is_break() {
+            return result;
+        }
+        self.body.walk_exprs(visitor)
+    }
+
+    fn walk_exprs_mut<R: VisitResult, V: ExprVisitorMut<R> + ?Sized>(
+        &mut self,
+        visitor: &mut V,
+    ) -> R {
+        let result = self.params.walk_exprs_mut(visitor);
+        if result.is_break() {
+            return result;
+        }
+        self.body.walk_exprs_mut(visitor)
+    }
+
+    fn walk_stmts<R: VisitResult, V: StmtVisitor<R> + ?Sized>(&self, visitor: &mut V) -> R {
+        let result = self.params.walk_stmts(visitor);
+        if result.is_break() {
+            return result;
+        }
+        self.body.walk_stmts(visitor)
+    }
+
+    fn walk_stmts_mut<R: VisitResult, V: StmtVisitorMut<R> + ?Sized>(
+        &mut self,
+        visitor: &mut V,
+    ) -> R {
+        let result = self.params.walk_stmts_mut(visitor);
+        if result.is_break() {
+            return result;
+        }
+        self.body.walk_stmts_mut(visitor)
+    }
+}
+
+impl Walk for Param {
+    fn walk_exprs<R: VisitResult, V: ExprVisitor<R> + ?Sized>(&self, visitor: &mut V) -> R {
+        self.default.walk_exprs(visitor)
+    }
+
+    fn walk_exprs_mut<R: VisitResult, V: ExprVisitorMut<R> + ?Sized>(
+        &mut self,
+        visitor: &mut V,
+    ) -> R {
+        self.default.walk_exprs_mut(visitor)
+    }
+
+    fn walk_stmts<R: VisitResult, V: StmtVisitor<R> + ?Sized>(&self, visitor: &mut V) -> R {
+        self.default.walk_stmts(visitor)
+    }
+
+    fn walk_stmts_mut<R: VisitResult, V: StmtVisitorMut<R> + ?Sized>(
+        &mut self,
+        visitor: &mut V,
+    ) -> R {
+        self.default.walk_stmts_mut(visitor)
+    }
+}
+
+/// Fields that hold no AST nodes.
+macro_rules! leaf {
+    ($($ty:ty),*) => {
+        $(impl Walk for $ty {
+            fn walk_exprs<R: VisitResult, V: ExprVisitor<R> + ?Sized>(&self, _: &mut V) -> R {
+                R::output()
+            }
+
+            fn walk_exprs_mut<R: VisitResult, V: ExprVisitorMut<R> + ?Sized>(
+                &mut self,
+                _: &mut V,
+            ) -> R {
+                R::output()
+            }
+
+            fn walk_stmts<R: VisitResult, V: StmtVisitor<R> + ?Sized>(&self, _: &mut V) -> R {
+                R::output()
+            }
+
+            fn walk_stmts_mut<R: VisitResult, V: StmtVisitorMut<R> + ?Sized>(
+                &mut self,
+                _: &mut V,
+            ) -> R {
+                R::output()
+            }
+        })*
+    };
+}
+
+leaf!(Token, ValueWrapper, usize);
@@ -0,0 +1,205 @@
+use core::{natives::globals, TokenType, ValueWrapper};
+
+/// Calls the native conversion `name` on `value`, giving the result's debug form, since
+/// `==` finds `1` and `1.0` equal, or the error message.
+fn convert(name: &str, value: ValueWrapper) -> String {
+    let ValueWrapper::Func(function) = &globals()[name] else {
+        panic!("`{}` isn't a function", name)
+    };
+    match function.call(vec![value]) {
+        Ok(value) => format!("{:?}", value),
+        Err(err) => err.to_string(),
+    }
+}
+
+/// Applies `operator` to both operands, in the same form as [`convert`].
+fn binary(left: ValueWrapper, operator: TokenType, right: ValueWrapper) -> String {
+    match left.binary(&operator, &right) {
+        Ok(Some(value)) => format!("{:?}", value),
+        Ok(None) => String::from("undefined"),
+        Err(err) => err.to_string(),
+    }
+}
+
+fn big(digits: &str) -> ValueWrapper {
+    ValueWrapper::BigInt(digits.parse().unwrap())
+}
+
+fn dec(digits: &str) -> ValueWrapper {
+    ValueWrapper::Decimal(digits.parse().unwrap())
+}
+
+fn str(s: &str) -> ValueWrapper {
+    ValueWrapper::Str(s.to_string())
+}
+
+#[test]
+fn int_truncates_numbers_and_numeric_strings() {
+    use ValueWrapper::*;
+    assert_eq!(convert("int", Num(3.9)), "Int(3)");
+    assert_eq!(convert("int", Num(-3.9)), "Int(-3)");
+    assert_eq!(convert("int", big("12")), "Int(12)");
+    assert_eq!(convert("int", dec("-1.99")), "Int(-1)");
+    assert_eq!(convert("int", str(" 42 ")), "Int(42)");
+    assert_eq!(convert("int", str("12.7")), "Int(12)");
+    assert_eq!(convert("int", str("-1.5e2")), "Int(-150)");
+    assert_eq!(convert("int", str("1E3")), "Int(1000)");
+    assert_eq!(convert("int", str("15e-1")), "Int(1)");
+    assert_eq!(
+        convert("int", str("9223372036854775807")),
+        "Int(9223372036854775807)"
+    );
+}
+
+#[test]
+fn int_rejects_what_it_cannot_hold() {
+    use ValueWrapper::*;
+    assert_eq!(
+        convert("int", str("9223372036854775808")),
+        "`\"9223372036854775808\"` can't be converted to an integer."
+    );
+    assert_eq!(
+        convert("int", Num(1e19)),
+        "`1e19` can't be converted to an integer."
+    );
+    assert_eq!(
+        convert("int", Num(f64::NAN)),
+        "`NaN` can't be converted to an integer."
+    );
+    assert_eq!(
+        convert("int", str("1.5.2")),
+        "`\"1.5.2\"` can't be converted to an integer."
+    );
+    assert_eq!(
+        convert("int", Nil),
+        "`nil` can't be converted to an integer."
+    );
+}
+
+#[test]
+fn float_rounds_to_the_nearest_float() {
+    use ValueWrapper::*;
+    assert_eq!(convert("float", Int(3)), "Num(3.0)");
+    assert_eq!(convert("float", big("12")), "Num(12.0)");
+    assert_eq!(convert("float", dec("0.1")), "Num(0.1)");
+    assert_eq!(convert("float", str("1.5e3")), "Num(1500.0)");
+    assert_eq!(
+        convert("float", Int(9007199254740993)),
+        "Num(9007199254740992.0)"
+    );
+    assert_eq!(
+        convert("float", str("one")),
+        "`\"one\"` can't be converted to a float."
+    );
+}
+
+#[test]
+fn bigint_parses_strings_exactly() {
+    use ValueWrapper::*;
+    assert_eq!(
+        convert("bigint", str("123456789012345678901234567890")),
+        "BigInt(123456789012345678901234567890)"
+    );
+    assert_eq!(
+        convert("bigint", str("1.5e30")),
+        "BigInt(1500000000000000000000000000000)"
+    );
+    assert_eq!(convert("bigint", str("-7.9")), "BigInt(-7)");
+    assert_eq!(convert("bigint", str("1e-99999")), "BigInt(0)");
+    assert_eq!(
+        convert("bigint", Num(1e20)),
+        "BigInt(100000000000000000000)"
+    );
+    assert_eq!(convert("bigint", dec("-2.5")), "BigInt(-2)");
+    assert_eq!(convert("bigint", Int(-5)), "BigInt(-5)");
+    assert_eq!(
+        convert("bigint", str("1e99999")),
+        "`\"1e99999\"` can't be converted to a big integer."
+    );
+    assert_eq!(
+        convert("bigint", Num(f64::INFINITY)),
+        "`inf` can't be converted to a big integer."
+    );
+}
+
+#[test]
+fn decimal_keeps_the_digits_written() {
+    use ValueWrapper::*;
+    assert_eq!(convert("decimal", Num(0.1)), "Decimal(0.1)");
+    assert_eq!(convert("decimal", Int(3)), "Decimal(3)");
+    assert_eq!(convert("decimal", big("12")), "Decimal(12)");
+    assert_eq!(convert("decimal", str("1.25")), "Decimal(1.25)");
+    assert_eq!(convert("decimal", str("2.5e-3")), "Decimal(0.0025)");
+    assert_eq!(convert("decimal", str("-4E2")), "Decimal(-400)");
+    assert_eq!(
+        convert("decimal", str("1e40")),
+        "`\"1e40\"` can't be converted to a decimal."
+    );
+    assert_eq!(
+        convert("decimal", Num(1e40)),
+        "`1e40` can't be converted to a decimal."
+    );
+    assert_eq!(
+        convert("decimal", big("123456789012345678901234567890")),
+        "`123456789012345678901234567890n` can't be converted to a decimal."
+    );
+}
+
+#[test]
+fn big_integer_arithmetic() {
+    use {TokenType::*, ValueWrapper::*};
+    assert_eq!(
+        binary(Int(i64::MAX), Plus, big("1")),
+        "BigInt(9223372036854775808)"
+    );
+    assert_eq!(binary(big("1"), Plus, Int(1)), "BigInt(2)");
+    assert_eq!(binary(big("7"), TildeSlash, big("-2")), "BigInt(-4)");
+    assert_eq!(binary(big("7"), Percent, big("-2")), "BigInt(-1)");
+    assert_eq!(binary(big("-7"), Percent, Int(2)), "BigInt(1)");
+    assert_eq!(binary(big("7"), Slash, big("2")), "Num(3.5)");
+    assert_eq!(binary(big("1"), Less, Num(1.5)), "Bool(true)");
+    assert_eq!(binary(big("2"), Star, Num(1.5)), "Num(3.0)");
+    assert_eq!(
+        binary(big("5"), TildeSlash, big("0")),
+        "Integer division by zero."
+    );
+    assert_eq!(
+        binary(big("5"), Percent, Int(0)),
+        "Integer division by zero."
+    );
+    assert_eq!(
+        binary(Int(i64::MAX), Plus, Int(1)),
+        "Integer overflow in `9223372036854775807 + 1`, use big integers like `1n` for larger numbers."
+    );
+}
+
+#[test]
+fn decimal_arithmetic() {
+    use {TokenType::*, ValueWrapper::*};
+    assert_eq!(binary(dec("0.1"), Plus, dec("0.2")), "Decimal(0.3)");
+    assert_eq!(
+        binary(dec("1"), Slash, dec("3")),
+        "Decimal(0.3333333333333333333333333333)"
+    );
+    assert_eq!(binary(dec("7"), TildeSlash, dec("-2")), "Decimal(-4)");
+    assert_eq!(binary(dec("7"), Percent, dec("-2")), "Decimal(-1)");
+    assert_eq!(binary(dec("1.5"), Star, Int(2)), "Decimal(3.0)");
+    assert_eq!(binary(dec("1"), Plus, big("1")), "Decimal(2)");
+    assert_eq!(binary(dec("0.5"), Less, Int(1)), "Bool(true)");
+    assert_eq!(
+        binary(dec("1"), Plus, Num(1.0)),
+        "Can't mix a decimal with a float in `1d + 1.0`, convert one of them with `decimal` or `float` first."
+    );
+    assert_eq!(
+        binary(dec("1"), Slash, dec("0")),
+        "Decimal division by zero."
+    );
+    assert_eq!(
+        binary(dec("1"), Plus, big("123456789012345678901234567890")),
+        "Decimal overflow in `1d + 123456789012345678901234567890n`."
+    );
+    assert_eq!(
+        binary(dec("79228162514264337593543950335"), Star, Int(2)),
+        "Decimal overflow in `79228162514264337593543950335d * 2`."
+    );
+}
@@ -0,0 +1,247 @@
+use core::{typings::TokenType::*, *};
+use lexer::Lexer;
+use parser::Parser;
+
+const INDENT: &str = "  ";
+
+/// What an open bracket belongs to, which decides how the tokens inside it are laid out.
+#[derive(Clone, Copy, PartialEq)]
+enum Group {
+    /// A block of statements, each on its own line.
+    Block,
+    /// A map literal, kept on one line.
+    Map,
+    /// Parentheses or square brackets.
+    Bracket,
+}
+
+/// Re-emits source code with consistent indentation, spacing and brace placement.
+///
+/// It works on the token stream rather than the AST, so everything the parser desugars
+/// (such as `for` loops and compound assignments) is written back the way it was typed,
+/// and comments stay where they were relative to the tokens around them.
+pub struct Formatter<'a> {
+    source: &'a str,
+    comments: Vec<Span>,
+    next_comment: usize,
+    out: String,
+    groups: Vec<Group>,
+    /// Whether the next token has to start a new line.
+    newline: bool,
+    /// The previous token, and whether it was a prefix operator.
+    prev: Option<(TokenType, bool)>,
+    /// Whether the next `|` closes the parameters of a lambda.
+    in_pipes: bool,
+    /// The source line the previous token or comment ended on.
+    last_line: usize,
+}
+
+impl<'a> Formatter<'a> {
+    /// Formats `source`, returning the lexer or parser errors instead if it isn't valid.
+    /// Formatting already formatted code gives back the same code.
+    pub fn format(source: &'a str) -> std::result::Result<String, Vec<SolisError>> {
+        let (tokens, errors) = Lexer::lex_lossless(source);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        let comments = tokens
+            .iter()
+            .flat_map(|token| token.leading.iter().chain(&token.trailing))
+            .filter(|trivia| {
+                matches!(
+                    trivia.kind,
+                    TriviaKind::LineComment | TriviaKind::BlockComment
+                )
+            })
+            .map(|trivia| trivia.span)
+            .collect();
+        let tokens: Vec<Token> = tokens.into_iter().map(|token| token.token).collect();
+        let (_, errors) = Parser::new(tokens.clone()).parse();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let mut slf = Self {
+            source,
+            comments,
+            next_comment: 0,
+            out: String::with_capacity(source.len()),
+            groups: Vec::new(),
+            newline: false,
+            prev: None,
+            in_pipes: false,
+            last_line: 1,
+        };
+        for (i, token) in tokens.iter().enumerate() {
+            slf.comments_before(token.span.start);
+            match tokens.get(i + 1) {
+                Some(next) => slf.token(token, next),
+                None => break,
+            }
+        }
+
+        if !slf.out.is_empty() {
+            slf.out.push('\n');
+        }
+        Ok(slf.out)
+    }
+
+    fn text(&self, span: Span) -> &'a str {
+        &self.source[span.start..span.end]
+    }
+
+    /// Starts a new line at the current indentation, keeping a single blank line where
+    /// the source had any before `line`. A line that continues a map or brackets, which
+    /// only happens after a comment, is indented one level further.
+    fn start_line(&mut self, line: usize, closes_block: bool) {
+        self.newline = false;
+        if self.out.is_empty() {
+            return;
+        }
+        self.out.push('\n');
+        if !closes_block && line > self.last_line + 1 && !self.out.ends_with("{\n") {
+            self.out.push('\n');
+        }
+        let blocks = self
+            .groups
+            .iter()
+            .filter(|&&group| group == Group::Block)
+            .count();
+        let continuation = !closes_block
+            && self
+                .groups
+                .last()
+                .is_some_and(|&group| group != Group::Block);
+        self.out
+            .push_str(&INDENT.repeat(blocks + continuation as usize));
+    }
+
+    /// Writes the comments that come before `offset`. One on the same line as the
+    /// previous token stays at the end of that line, any other gets its own line.
+    fn comments_before(&mut self, offset: usize) {
+        while let Some(&span) = self.comments.get(self.next_comment) {
+            if span.start >= offset {
+                break;
+            }
+            self.next_comment += 1;
+
+            let text = self.text(span).trim_end();
+            if !self.out.is_empty() && span.line == self.last_line {
+                self.out.push(' ');
+                // A line comment runs to the end of the line, so code can't follow it.
+                self.newline |= text.starts_with("//");
+            } else {
+                self.start_line(span.line, false);
+                self.newline = true;
+            }
+            self.out.push_str(text);
+            self.last_line = span.line + text.matches('\n').count();
+        }
+    }
+
+    fn has_comment_before(&self, offset: usize) -> bool {
+        self.comments
+            .get(self.next_comment)
+            .is_some_and(|comment| comment.start < offset)
+    }
+
+    fn token(&mut self, token: &Token, next: &Token) {
+        let closes_block = token.ty == RightBrace && self.groups.last() == Some(&Group::Block);
+        if matches!(token.ty, RightBrace | RightParen | RightBracket) {
+            self.groups.pop();
+        }
+        // Closing a non-empty block always starts a new line.
+        self.newline |= closes_block && self.prev.as_ref().map(|(ty, _)| ty) != Some(&LeftBrace);
+
+        if self.newline {
+            self.start_line(token.span.line, closes_block);
+        } else if self.spaced(token) {
+            self.out.push(' ');
+        }
+
+        let text = self.text(token.span);
+        self.out.push_str(text);
+        self.last_line = token.span.line + text.matches('\n').count();
+
+        let prefix = match token.ty {
+            Bang => true,
+            Minus => !self.prev.as_ref().is_some_and(|(ty, _)| {
+                matches!(
+                    ty,
+                    Identifier
+                        | NumberLiteral
+                        | StringLiteral
+                        | InterpolationEnd
+                        | BoolLiteral
+                        | NilLiteral
+                        | This
+                        | RightParen
+                        | RightBracket
+                )
+            }),
+            _ => false,
+        };
+
+        match token.ty {
+            LeftBrace => {
+                let group = match self.prev {
+                    None => Group::Block,
+                    Some((LeftBrace, _)) => *self.groups.last().unwrap_or(&Group::Block),
+                    Some((RightParen | RightBrace | Semicolon | Identifier | Else, _)) => {
+                        Group::Block
+                    }
+                    Some(_) => Group::Map,
+                };
+                self.groups.push(group);
+                // Empty blocks stay as `{}`.
+                self.newline = group == Group::Block
+                    && (next.ty != RightBrace || self.has_comment_before(next.span.start));
+            }
+            LeftParen | LeftBracket => self.groups.push(Group::Bracket),
+            Semicolon => {
+                // Unlike the ones in a `for` loop's header, these end a statement. An
+                // `else` stays on the line of the statement it follows.
+                self.newline =
+                    matches!(self.groups.last(), None | Some(Group::Block)) && next.ty != Else;
+            }
+            RightBrace if closes_block => {
+                self.newline = !matches!(
+                    next.ty,
+                    Semicolon | RightParen | RightBracket | Comma | Dot | LeftParen | Else
+                );
+            }
+            Pipe => self.in_pipes = !self.in_pipes,
+            _ => (),
+        }
+        self.prev = Some((token.ty.clone(), prefix));
+    }
+
+    /// Whether a space goes between the previous token and `token` on the same line.
+    fn spaced(&self, token: &Token) -> bool {
+        let Some((prev, prefix)) = &self.prev else {
+            return false;
+        };
+        if *prefix {
+            return false;
+        }
+
+        match (prev, &token.ty) {
+            (LeftParen | LeftBracket | LeftBrace | Dot | Ellipsis, _) => false,
+            (_, RightParen | RightBracket | RightBrace | Comma | Semicolon | Dot | Colon) => false,
+            // Calls and indexing sit right after what they apply to.
+            (
+                Identifier | StringLiteral | InterpolationEnd | This | RightParen | RightBracket
+                | RightBrace,
+                LeftParen | LeftBracket,
+            ) => false,
+            // As do interpolated expressions inside their `${` and `}`.
+            (Interpolation, _) => false,
+            (_, InterpolationEnd) => false,
+            (_, Interpolation) if token.lexeme.starts_with('}') => false,
+            // The parameters of a lambda sit right inside its pipes.
+            (Pipe, _) if self.in_pipes => false,
+            (_, Pipe) if self.in_pipes => false,
+            _ => true,
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use core::TriviaKind;
+use formatter::Formatter;
+use lexer::Lexer;
+
+/// Programs covering every construct the formatter lays out, written untidily.
+const PROGRAMS: &[&str] = &[
+    include_str!("../../../example.hl"),
+    r#"
+// A class with a superclass.
+class Animal {
+  init(name) { this.name = name; }
+  speak() { return "${this.name} makes a sound"; }
+}
+class Dog < Animal {
+    speak() {
+        /* Dogs bark. */
+        return super.speak() + " (woof)";
+    }
+}
+print Dog("Rex").speak(); // Rex makes a sound (woof)
+"#,
+    r#"
+var xs=[1,2,3];  var m = {"a": 1, // first
+"b": [1, 2] /* second */};
+for (var i = 0; i < len(xs); i = i + 1) { xs[i] += -1; }
+
+
+while (true) { if (xs[0] > 0) break; else continue; }
+if (m["a"]) print "a"; else if (m["b"]) print "b"; else print "neither";
+"#,
+    r#"
+fn apply(f, x = 1, ...rest) { return f(x); }
+print apply(|x| x * 2, x: 21);
+print apply(fn (x) {
+  // Nested blocks indent inside brackets.
+  return x;
+});
+fn empty() {}
+var s = r"raw ${not} interpolated" + "${1 + 2} and ${ "nested ${3}" }";
+print 0x1F + 1_000 + 1.5e3 + 7 ~/ 2 + 7 % 3 + 12n + 19.99d;
+"#,
+];
+
+fn format(source: &str) -> String {
+    Formatter::format(source).unwrap_or_else(|errors| panic!("{:?}", errors))
+}
+
+fn comments(source: &str) -> Vec<String> {
+    let (tokens, _) = Lexer::lex_lossless(source);
+    tokens
+        .iter()
+        .flat_map(|token| token.leading.iter().chain(&token.trailing))
+        .filter(|trivia| {
+            matches!(
+                trivia.kind,
+                TriviaKind::LineComment | TriviaKind::BlockComment
+            )
+        })
+        .map(|trivia| trivia.text.trim_end().to_string())
+        .collect()
+}
+
+#[test]
+fn formatting_is_idempotent() {
+    for program in PROGRAMS {
+        let formatted = format(program);
+        assert_eq!(format(&formatted), formatted, "{}", formatted);
+    }
+}
+
+#[test]
+fn comments_are_preserved_in_order() {
+    for program in PROGRAMS {
+        assert_eq!(comments(&format(program)), comments(program));
+    }
+}
+
+#[test]
+fn continuation_lines_are_indented() {
+    let source = "var m = {\"a\": 1, // first\n\"b\": 2 /* second */};\nfoo(1, // one\n2);\n";
+    assert_eq!(
+        format(source),
+        "var m = {\"a\": 1, // first\n  \"b\": 2 /* second */};\nfoo(1, // one\n  2);\n"
+    );
+}
+
+#[test]
+fn else_stays_after_a_statement() {
+    assert_eq!(format("if (c) a;\nelse b;"), "if (c) a; else b;\n");
+    assert_eq!(
+        format("if (c) { a; } else { b; }"),
+        "if (c) {\n  a;\n} else {\n  b;\n}\n"
+    );
+}
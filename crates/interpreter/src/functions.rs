@@ -1,41 +1,101 @@
-use crate::InterpretStmt;
+use crate::{ExecResult, InterpretExpr, InterpretStmt};
 use core::*;
-use std::rc::Rc;
+use std::{cell::RefCell, rc::Rc};
 
 #[derive(Debug)]
 pub struct SolisFunction {
     pub name: String,
-    params: Vec<Token>,
-    body: Box<Stmt>,
+    declaration: Rc<FunctionDecl>,
+    closure: EnvData,
+    is_initializer: bool,
 }
 impl SolisFunction {
-    pub fn new(name: Token, params: Vec<Token>, body: Box<Stmt>) -> Rc<Self> {
+    pub fn new(
+        name: &str,
+        declaration: Rc<FunctionDecl>,
+        closure: EnvData,
+        is_initializer: bool,
+    ) -> Rc<Self> {
         Rc::new(SolisFunction {
-            name: name.lexeme,
-            params,
-            body,
+            name: name.to_string(),
+            declaration,
+            closure,
+            is_initializer,
         })
     }
+
+    fn this(&self) -> Result<ValueWrapper> {
+        self.closure.borrow().get_at(0, "this", Span::default())
+    }
 }
 impl Callable for SolisFunction {
-    fn arity(&self) -> usize {
-        self.params.len()
+    fn arity(&self) -> Arity {
+        self.declaration.arity()
+    }
+
+    fn call(&self, arguments: Vec<ValueWrapper>) -> Result<ValueWrapper> {
+        self.call_named(arguments, Vec::new())
     }
 
-    fn call(&self, arguments: Vec<ValueWrapper>, env: EnvData) -> Result<ValueWrapper> {
-        let environment = Environment::new_with_enclosing(env);
-        for (i, item) in arguments.iter().enumerate() {
+    fn call_named(
+        &self,
+        arguments: Vec<ValueWrapper>,
+        named: Vec<(String, ValueWrapper)>,
+    ) -> Result<ValueWrapper> {
+        let declaration = &self.declaration;
+        let (values, extra) = bind_arguments(
+            &self.name,
+            declaration
+                .params
+                .iter()
+                .map(|param| param.name.lexeme.as_str()),
+            declaration.required(),
+            arguments,
+            named,
+        )?;
+
+        let environment = Environment::new_with_enclosing(self.closure.clone());
+        for (param, value) in declaration.params.iter().zip(values) {
+            let value = match (value, &param.default) {
+                (Some(value), _) => value,
+                (None, Some(default)) => default.evaluate(environment.clone())?,
+                (None, None) => unreachable!("missing required arguments are rejected"),
+            };
             environment
                 .borrow_mut()
-                .define(self.params[i].lexeme.clone(), item.clone());
+                .define(param.name.lexeme.clone(), value);
+        }
+        if let Some(rest) = &declaration.rest {
+            let extra = ValueWrapper::List(Rc::new(RefCell::new(extra)));
+            environment.borrow_mut().define(rest.lexeme.clone(), extra);
         }
 
-        let output = self.body.evaluate(environment);
-        match output {
-            Ok(_) => Ok(ValueWrapper::Nil),
-            Err(SolisError::Return { location: _, value }) => Ok(value),
-            Err(e) => Err(e),
+        for statement in declaration.body.iter() {
+            match statement.evaluate(environment.clone())? {
+                ExecResult::Return(_) if self.is_initializer => return self.this(),
+                ExecResult::Return(value) => return Ok(value),
+                _ => (),
+            }
         }
+
+        if self.is_initializer {
+            return self.this();
+        }
+        Ok(ValueWrapper::Nil)
+    }
+
+    fn bind(self: Rc<Self>, instance: ValueWrapper) -> Rc<dyn Callable> {
+        let environment = Environment::new_with_enclosing(self.closure.clone());
+        environment
+            .borrow_mut()
+            .define(String::from("this"), instance);
+
+        Rc::new(SolisFunction {
+            name: self.name.clone(),
+            declaration: self.declaration.clone(),
+            closure: environment,
+            is_initializer: self.is_initializer,
+        })
     }
 
     fn name(&self) -> &str {
@@ -1,10 +1,23 @@
-#![feature(if_let_guard)]
 pub mod functions;
 
+use crate::functions::SolisFunction;
+
 use core::{TokenType::*, ValueWrapper::*, *};
+use rustc_hash::FxHashMap;
+use std::{cell::RefCell, rc::Rc};
+
+/// How a statement finished executing. Anything other than `Normal` unwinds the
+/// enclosing blocks until a loop or function call consumes it.
+#[derive(Debug)]
+pub enum ExecResult {
+    Normal,
+    Break,
+    Continue,
+    Return(ValueWrapper),
+}
 
 pub trait InterpretStmt {
-    fn evaluate(&self, env: EnvData) -> Result<()>;
+    fn evaluate(&self, env: EnvData) -> Result<ExecResult>;
 }
 
 pub trait InterpretExpr {
@@ -22,71 +35,123 @@ pub trait InterpretExpr {
 }
 
 impl InterpretStmt for Stmt {
-    fn evaluate(&self, env: EnvData) -> Result<()> {
+    fn evaluate(&self, env: EnvData) -> Result<ExecResult> {
         match self {
-            Stmt::Expression { expression } => {
+            Stmt::Expression { expression, .. } => {
                 expression.evaluate(env)?;
             }
-            Stmt::Print { expression } => {
+            Stmt::Print { expression, .. } => {
                 println!("{}", expression.evaluate(env)?);
             }
-            Stmt::Var { name, inititalizer } => {
-                if let Some(initalizer) = inititalizer {
-                    let val = initalizer.evaluate(env.clone())?;
-                    env.borrow_mut().define(name.to_string(), val);
-                }
+            Stmt::Var {
+                name, inititalizer, ..
+            } => {
+                let val = match inititalizer {
+                    Some(initalizer) => initalizer.evaluate(env.clone())?,
+                    None => Nil,
+                };
+                env.borrow_mut().define(name.to_string(), val);
             }
-            Stmt::Block { statements } => {
+            Stmt::Block { statements, .. } => {
                 let env = Environment::new_with_enclosing(env);
                 for statement in statements {
-                    statement.evaluate(env.clone())?;
+                    match statement.evaluate(env.clone())? {
+                        ExecResult::Normal => (),
+                        result => return Ok(result),
+                    }
                 }
-                drop(env)
             }
             Stmt::IfStmt {
                 condition,
                 then_branch,
                 else_branch,
+                ..
             } => {
                 if condition.evaluate(env.clone())? == true.into() {
-                    then_branch.evaluate(env)?;
+                    return then_branch.evaluate(env);
                 } else if let Some(else_branch) = else_branch {
-                    else_branch.evaluate(env)?;
+                    return else_branch.evaluate(env);
                 }
             }
-            Stmt::WhileStmt { condition, body } => {
+            Stmt::WhileStmt {
+                condition,
+                body,
+                increment,
+                ..
+            } => {
                 while condition.evaluate(env.clone())? == Bool(true) {
-                    match body.evaluate(env.clone()) {
-                        Err(SolisError::Break { .. }) => break,
-                        Err(SolisError::Continue { .. }) => continue,
-                        Err(e) => return Err(e),
-                        _ => (),
+                    match body.evaluate(env.clone())? {
+                        ExecResult::Break => break,
+                        ExecResult::Return(value) => return Ok(ExecResult::Return(value)),
+                        ExecResult::Continue | ExecResult::Normal => (),
+                    }
+                    if let Some(increment) = increment {
+                        increment.evaluate(env.clone())?;
                     }
                 }
             }
-            Stmt::BreakStmt { location } => {
-                return Err(SolisError::Break {
-                    location: location.clone(),
-                })
+            Stmt::BreakStmt { .. } => return Ok(ExecResult::Break),
+            Stmt::ContinueStmt { .. } => return Ok(ExecResult::Continue),
+            Stmt::Function { name, function, .. } => {
+                let function =
+                    SolisFunction::new(&name.lexeme, function.clone(), env.clone(), false);
+                env.borrow_mut().define(name.lexeme.clone(), Func(function));
             }
-            Stmt::ContinueStmt { location } => {
-                return Err(SolisError::Continue {
-                    location: location.clone(),
-                })
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+                ..
+            } => {
+                let superclass = match superclass {
+                    Some(superclass) => match superclass.evaluate(env.clone())? {
+                        ValueWrapper::Class(class) => Some(class),
+                        _ => {
+                            return Err(SolisError::RuntimeError(
+                                superclass.span(),
+                                String::from("Superclass must be a class."),
+                            ))
+                        }
+                    },
+                    None => None,
+                };
+
+                let mut closure = env.clone();
+                if let Some(superclass) = &superclass {
+                    closure = Environment::new_with_enclosing(env.clone());
+                    closure.borrow_mut().define(
+                        String::from("super"),
+                        ValueWrapper::Class(superclass.clone()),
+                    );
+                }
+
+                let mut class_methods = FxHashMap::default();
+                for method in methods {
+                    if let Stmt::Function { name, function, .. } = method {
+                        let function: Rc<dyn Callable> = SolisFunction::new(
+                            &name.lexeme,
+                            function.clone(),
+                            closure.clone(),
+                            name.lexeme == "init",
+                        );
+                        class_methods.insert(name.lexeme.clone(), function);
+                    }
+                }
+
+                let class = SolisClass::new(name.lexeme.clone(), superclass, class_methods);
+                env.borrow_mut()
+                    .define(name.lexeme.clone(), ValueWrapper::Class(class));
             }
-            Stmt::Function { instance } => env
-                .borrow_mut()
-                .define(instance.name().to_string(), Func(instance.clone())),
-            Stmt::ReturnStmt { keyword, value } => {
-                let value = value.evaluate(env)?;
-                return Err(SolisError::Return {
-                    location: keyword.clone(),
-                    value,
-                });
+            Stmt::ReturnStmt { value, .. } => {
+                let value = match value {
+                    Some(value) => value.evaluate(env)?,
+                    None => Nil,
+                };
+                return Ok(ExecResult::Return(value));
             }
         }
 
-        Ok(())
+        Ok(ExecResult::Normal)
     }
 }
 
@@ -101,41 +166,15 @@ impl InterpretExpr for Expr {
         let left = left.evaluate(env.clone())?;
         let right = right.evaluate(env)?;
 
-        match operator.ty {
-            BangEqual => return Ok((!(left == right)).into()),
-            EqualEqual => return Ok((left == right).into()),
-            _ => (),
-        }
-
-        match (&left.try_into_num(), &right.try_into_num()) {
-            (Num(l), Num(r)) => match &operator.ty {
-                Greater => return Ok((l > r).into()),
-                GreaterEqual => return Ok((l >= r).into()),
-                Less => return Ok((l < r).into()),
-                LessEqual => return Ok((l <= r).into()),
-
-                Minus => return Ok((l - r).into()),
-                Star => return Ok((l * r).into()),
-                Slash => return Ok((l / r).into()),
-                Plus => return Ok((l + r).into()),
-                _ => (),
-            },
-            (Str(left), Str(right)) if operator.ty == Plus => {
-                return Ok((left.clone() + right).into())
-            }
-            (Num(left), Str(right)) if operator.ty == Plus => {
-                return Ok((left.to_string() + right).into())
-            }
-            (Str(left), Num(right)) if operator.ty == Plus => {
-                return Ok((left.to_owned() + &right.to_string()).into())
-            }
-            _ => (),
-        }
-
-        Err(SolisError::RuntimeError(
-            operator.line,
-            format!("Invalid equation: {:?} `{}` {:?}", left, operator, right),
-        ))
+        let result = left.binary(&operator.ty, &right);
+        result
+            .map_err(|err| err.or_span(self.span()))?
+            .ok_or_else(|| {
+                SolisError::RuntimeError(
+                    self.span(),
+                    format!("Invalid equation: {:?} `{}` {:?}", left, operator, right),
+                )
+            })
     }
 
     fn evaluate_grouping(&self, expr: &Expr, env: EnvData) -> Result<ValueWrapper> {
@@ -148,19 +187,18 @@ impl InterpretExpr for Expr {
 
     fn evaluate_unary(&self, operator: &Token, right: &Expr, env: EnvData) -> Result<ValueWrapper> {
         let right = right.evaluate(env)?;
-        match operator.ty {
-            Minus if let Num(x) = &right => {
-                Ok((*x).into())
-            },
-            Bang => {
-                Ok(match right {
-                    Bool(x) => !x,
-                    Nil => false,
-                    _ => true
-                }.into())
-            }
-            _ => Err(SolisError::RuntimeError(operator.line, format!("Unrecognized operator `{}` with value `{}`.", operator, right), )),
-        }
+        let result = right.unary(&operator.ty);
+        result
+            .map_err(|err| err.or_span(self.span()))?
+            .ok_or_else(|| {
+                SolisError::RuntimeError(
+                    self.span(),
+                    format!(
+                        "Unrecognized operator `{}` with value `{}`.",
+                        operator, right
+                    ),
+                )
+            })
     }
 
     fn evaluate(&self, env: EnvData) -> Result<ValueWrapper> {
@@ -169,13 +207,15 @@ impl InterpretExpr for Expr {
                 left,
                 operator,
                 right,
+                ..
             } => self.evaluate_binary(left, operator, right, env),
-            Expr::Grouping { expression } => self.evaluate_grouping(expression, env),
-            Expr::Literal { value } => self.evaluate_literal(value, env),
+            Expr::Grouping { expression, .. } => self.evaluate_grouping(expression, env),
+            Expr::Literal { value, .. } => self.evaluate_literal(value, env),
             Expr::Logical {
                 left,
                 operator,
                 right,
+                ..
             } => {
                 let left = left.evaluate(env.clone())?;
 
@@ -189,42 +229,204 @@ impl InterpretExpr for Expr {
 
                 right.evaluate(env)
             }
-            Expr::Unary { operator, right } => self.evaluate_unary(operator, right, env),
+            Expr::Unary {
+                operator, right, ..
+            } => self.evaluate_unary(operator, right, env),
             Expr::Call {
                 callee,
                 paren,
                 args,
+                named,
+                ..
             } => {
                 let callee = callee.evaluate(env.clone())?;
                 let mut arguments = Vec::with_capacity(args.capacity());
                 for argument in args {
                     arguments.push(argument.evaluate(env.clone())?)
                 }
+                let mut named_arguments = Vec::with_capacity(named.len());
+                for (name, argument) in named {
+                    named_arguments.push((name.lexeme.clone(), argument.evaluate(env.clone())?));
+                }
+                let count = arguments.len() + named_arguments.len();
 
-                let Func(function) = callee else {
-                    return Err(SolisError::RuntimeError(paren.line, String::from("Can only call functions and classes.")))
+                let arity = match &callee {
+                    Func(function) => function.arity(),
+                    ValueWrapper::Class(class) => class.arity(),
+                    _ => {
+                        return Err(SolisError::RuntimeError(
+                            paren.span,
+                            String::from("Can only call functions and classes."),
+                        ))
+                    }
                 };
 
-                if arguments.len() != function.arity() {
+                if !arity.accepts(count) {
                     return Err(SolisError::RuntimeError(
-                        paren.line,
+                        self.span(),
                         format!(
-                            "Expected {} arguments but recieved {} arguments.",
-                            function.arity(),
-                            arguments.len()
+                            "Expected {} arguments but received {} arguments.",
+                            arity, count
                         ),
                     ));
                 }
 
-                function.call(arguments, env)
+                match callee {
+                    ValueWrapper::Class(class) => {
+                        SolisClass::instantiate(&class, arguments, named_arguments)
+                    }
+                    Func(function) => function.call_named(arguments, named_arguments),
+                    _ => unreachable!(),
+                }
+                .map_err(|err| err.or_span(self.span()))
             }
-            Expr::Variable { name } => env.borrow().get(name.lexeme.clone(), name.line),
-            Expr::Assign { name, value } => {
+            Expr::Variable { name, depth, .. } => match depth {
+                Some(depth) => env.borrow().get_at(*depth, &name.lexeme, name.span),
+                None => env.borrow().get_global(&name.lexeme, name.span),
+            },
+            Expr::Assign {
+                name, value, depth, ..
+            } => {
                 let value = value.evaluate(env.clone())?;
-                env.borrow_mut()
-                    .assign(name.lexeme.clone(), value.clone(), name.line)?;
+                match depth {
+                    Some(depth) => env.borrow_mut().assign_at(
+                        *depth,
+                        &name.lexeme,
+                        value.clone(),
+                        name.span,
+                    )?,
+                    None => {
+                        env.borrow_mut()
+                            .assign_global(&name.lexeme, value.clone(), name.span)?
+                    }
+                }
+                Ok(value)
+            }
+            Expr::Get { object, name, .. } => match object.evaluate(env)? {
+                Instance(instance) => SolisInstance::get(&instance, &name.lexeme, name.span),
+                _ => Err(SolisError::RuntimeError(
+                    name.span,
+                    String::from("Only instances have properties."),
+                )),
+            },
+            Expr::Set {
+                object,
+                name,
+                value,
+                ..
+            } => {
+                let Instance(instance) = object.evaluate(env.clone())? else {
+                    return Err(SolisError::RuntimeError(
+                        name.span,
+                        String::from("Only instances have fields."),
+                    ));
+                };
+
+                let value = value.evaluate(env)?;
+                instance.borrow_mut().set(&name.lexeme, value.clone());
                 Ok(value)
             }
+            Expr::This { keyword, depth, .. } => match depth {
+                Some(depth) => env.borrow().get_at(*depth, &keyword.lexeme, keyword.span),
+                None => env.borrow().get_global(&keyword.lexeme, keyword.span),
+            },
+            Expr::SuperExpr {
+                keyword,
+                method,
+                depth,
+                ..
+            } => {
+                let depth = depth.unwrap_or_default();
+                // `this` is bound in the scope just inside the one holding `super`.
+                let Some(this_depth) = depth.checked_sub(1) else {
+                    return Err(SolisError::RuntimeError(
+                        keyword.span,
+                        String::from("`super` was not resolved to a class."),
+                    ));
+                };
+                let ValueWrapper::Class(superclass) =
+                    env.borrow().get_at(depth, "super", keyword.span)?
+                else {
+                    unreachable!()
+                };
+                let object = env.borrow().get_at(this_depth, "this", keyword.span)?;
+
+                match superclass.find_method(&method.lexeme) {
+                    Some(function) => Ok(Func(function.bind(object))),
+                    None => Err(SolisError::RuntimeError(
+                        method.span,
+                        format!("Undefined property `{}`.", method.lexeme),
+                    )),
+                }
+            }
+            Expr::List { elements, .. } => {
+                let mut list = Vec::with_capacity(elements.len());
+                for element in elements {
+                    list.push(element.evaluate(env.clone())?);
+                }
+                Ok(List(Rc::new(RefCell::new(list))))
+            }
+            Expr::Interpolation { parts, .. } => {
+                let mut string = String::new();
+                for part in parts {
+                    string.push_str(&part.evaluate(env.clone())?.stringify());
+                }
+                Ok(Str(string))
+            }
+            Expr::Map { entries, .. } => {
+                let mut map = SolisMap::default();
+                for (key, value) in entries {
+                    let key = MapKey::new(&key.evaluate(env.clone())?, key.span())?;
+                    map.insert(key, value.evaluate(env.clone())?);
+                }
+                Ok(Map(Rc::new(RefCell::new(map))))
+            }
+            Expr::Index { object, index, .. } => {
+                let object = object.evaluate(env.clone())?;
+                let index = index.evaluate(env)?;
+                object.get_index(&index, self.span())
+            }
+            Expr::SetIndex {
+                object,
+                index,
+                value,
+                operator,
+                ..
+            } => {
+                let object = object.evaluate(env.clone())?;
+                let index = index.evaluate(env.clone())?;
+
+                // The current element is read before the right-hand side runs, as with
+                // compound assignment to a variable.
+                let current = match operator {
+                    Some(_) => Some(object.get_index(&index, self.span())?),
+                    None => None,
+                };
+                let mut value = value.evaluate(env)?;
+                if let (Some(operator), Some(current)) = (operator, current) {
+                    let result = current.binary(&operator.ty, &value);
+                    value = result
+                        .map_err(|err| err.or_span(self.span()))?
+                        .ok_or_else(|| {
+                            SolisError::RuntimeError(
+                                self.span(),
+                                format!(
+                                    "Invalid equation: {:?} `{}` {:?}",
+                                    current, operator, value
+                                ),
+                            )
+                        })?;
+                }
+
+                object.set_index(&index, value.clone(), self.span())?;
+                Ok(value)
+            }
+            Expr::Lambda { function, .. } => Ok(Func(SolisFunction::new(
+                "lambda",
+                function.clone(),
+                env,
+                false,
+            ))),
         }
     }
 }
@@ -239,6 +441,24 @@ impl Interpreter {
         }
     }
 
+    /// Defines (or redefines) the global variable `name`.
+    pub fn define_global<T: Into<ValueWrapper>>(&mut self, name: &str, value: T) {
+        self.environment
+            .borrow_mut()
+            .define(name.to_string(), value.into());
+    }
+
+    /// Exposes a Rust closure to scripts as the global function `name`. The backend
+    /// checks the arity before calling it; argument types can be checked with
+    /// [`Arguments::get`].
+    pub fn register_fn<A, F>(&mut self, name: &str, arity: A, function: F)
+    where
+        A: Into<Arity>,
+        F: std::ops::Fn(Arguments) -> Result<ValueWrapper> + 'static,
+    {
+        self.define_global(name, Func(NativeFunction::new(name, arity, function)));
+    }
+
     pub fn interpret(&self, statements: Vec<Box<Stmt>>) -> Result<()> {
         for statement in statements {
             statement.evaluate(self.environment.clone())?;
@@ -1,17 +1,28 @@
 mod macros;
+mod trivia;
 use core::{typings::TokenType::*, *};
 use log::debug;
+use rust_decimal::Decimal;
 use std::{iter::Peekable, str::Chars};
 
 pub struct Lexer<'a> {
     source: Peekable<Chars<'a>>,
     buffer: String,
     result: Vec<Token>,
+    /// Comments are skipped by the parser, but kept for tools such as the formatter.
+    comments: Vec<Span>,
+    /// The start of every string whose `${` is still open, with the number of braces
+    /// opened inside it. Its `}` carries on with the rest of the string.
+    interpolations: Vec<(Span, usize)>,
 
     current: Option<char>,
     peeked: Option<char>,
 
     curr_line: usize,
+    curr_column: usize,
+    offset: usize,
+    /// Position of the first character of the token currently in `buffer`.
+    start: Span,
 }
 
 impl Iterator for Lexer<'_> {
@@ -19,9 +30,18 @@ impl Iterator for Lexer<'_> {
 
     fn next(&mut self) -> Option<Self::Item> {
         let (curr, peek) = (self.source.next(), self.source.peek().cloned());
-        if curr == Some('\n') {
-            self.curr_line += 1;
+        if self.buffer.is_empty() {
+            self.start = Span::new(self.offset, self.offset, self.curr_line, self.curr_column);
         }
+        match curr {
+            Some('\n') => {
+                self.curr_line += 1;
+                self.curr_column = 1;
+            }
+            Some(_) => self.curr_column += 1,
+            None => (),
+        }
+        self.offset += curr.map_or(0, char::len_utf8);
 
         self.current = curr;
         self.peeked = peek;
@@ -40,9 +60,14 @@ impl<'a> Lexer<'a> {
             source: source.chars().peekable(),
             buffer: String::with_capacity(10),
             result: Vec::new(),
+            comments: Vec::new(),
+            interpolations: Vec::new(),
             current: None,
             peeked: None,
             curr_line: 1,
+            curr_column: 1,
+            offset: 0,
+            start: Span::default(),
         }
     }
 
@@ -58,6 +83,30 @@ impl<'a> Lexer<'a> {
         self.add_token(ty, None);
     }
 
+    /// The character after `peeked`.
+    fn peek_second(&self) -> Option<char> {
+        self.source.clone().nth(1)
+    }
+
+    /// The span of `current`, which must not be a newline.
+    fn char_span(&self) -> Span {
+        let len = self.current.map_or(0, char::len_utf8);
+        Span::new(
+            self.offset - len,
+            self.offset,
+            self.curr_line,
+            self.curr_column - 1,
+        )
+    }
+
+    /// The span of the token currently in `buffer`.
+    fn span(&self) -> Span {
+        Span {
+            end: self.offset,
+            ..self.start
+        }
+    }
+
     fn clear_buffer(&mut self) {
         self.buffer.clear();
     }
@@ -67,25 +116,50 @@ impl<'a> Lexer<'a> {
             token_ty,
             self.buffer.clone(),
             literal,
-            self.curr_line,
+            self.span(),
         ));
 
         self.clear_buffer()
     }
 
-    pub fn lex(source: &'a str) -> Result<Vec<Token>> {
+    /// Scans `source` into tokens, skipping over anything it cannot lex. Returns the
+    /// tokens alongside every error encountered; the tokens should only be parsed if
+    /// there are no errors.
+    pub fn lex(source: &'a str) -> (Vec<Token>, Vec<SolisError>) {
+        let (tokens, _, errors) = Self::lex_with_comments(source);
+        (tokens, errors)
+    }
+
+    /// Like [`Lexer::lex`], but keeps every byte of `source`: whitespace, comments and
+    /// anything that failed to lex are attached to the tokens as trivia, so writing out
+    /// the tokens in order gives back `source` exactly.
+    pub fn lex_lossless(source: &'a str) -> (Vec<LosslessToken>, Vec<SolisError>) {
+        let (tokens, comments, errors) = Self::lex_with_comments(source);
+        (trivia::attach(source, tokens, comments), errors)
+    }
+
+    /// Like [`Lexer::lex`], but also returns the span of every comment, in source order.
+    fn lex_with_comments(source: &'a str) -> (Vec<Token>, Vec<Span>, Vec<SolisError>) {
         let mut slf = Self::new(source);
+        let mut errors = Vec::new();
         let start = std::time::Instant::now();
 
         while let Some(curr) = slf.next() {
-            slf.scan_token(curr)?
+            if let Err(err) = slf.scan_token(curr) {
+                errors.push(err);
+                slf.clear_buffer();
+            }
         }
 
+        for (span, _) in slf.interpolations.drain(..) {
+            errors.push(SolisError::UnterminatedString { span });
+        }
+        slf.start = Span::new(slf.offset, slf.offset, slf.curr_line, slf.curr_column);
         slf.add_token(TokenType::EOF, None);
 
         debug!("scanned {} lines in {:?}", slf.curr_line, start.elapsed());
 
-        Ok(slf.result.clone())
+        (slf.result, slf.comments, errors)
     }
 
     fn scan_token(&mut self, curr: char) -> Result<()> {
@@ -107,11 +181,30 @@ impl<'a> Lexer<'a> {
             // General characters
             '(' => add!(LeftParen),
             ')' => add!(RightParen),
-            '{' => add!(LeftBrace),
-            '}' => add!(RightBrace),
+            '{' => {
+                if let Some((_, braces)) = self.interpolations.last_mut() {
+                    *braces += 1;
+                }
+                add!(LeftBrace)
+            }
+            '}' => match self.interpolations.last_mut() {
+                Some((_, 0)) => {
+                    self.interpolations.pop();
+                    self.handle_string(InterpolationEnd)?
+                }
+                Some((_, braces)) => {
+                    *braces -= 1;
+                    add!(RightBrace)
+                }
+                None => add!(RightBrace),
+            },
+            '[' => add!(LeftBracket),
+            ']' => add!(RightBracket),
             ',' => add!(Comma),
-            '.' => add!(Dot),
+            ':' => add!(Colon),
+            '.' => self.handle_dot(),
             ';' => add!(Semicolon),
+            '|' => add!(Pipe),
             // Operators
             '!' => add_op!(Bang),
             '=' => add_op!(Equal),
@@ -121,14 +214,25 @@ impl<'a> Lexer<'a> {
             '+' => add_op!(Plus),
             '-' => add_op!(Minus),
             '*' => add_op!(Star),
+            '%' => add_op!(Percent),
+            '~' if self.peeked == Some('/') => {
+                self.next();
+                add_op!(TildeSlash)
+            }
             // Longer Lexemes
             '/' => self.handle_slash()?,
-            '"' => self.handle_string()?,
+            '"' => self.handle_string(StringLiteral)?,
+            'r' if self.peeked == Some('"') => self.handle_raw_string()?,
             '0'..='9' => self.handle_number()?,
             'a'..='z' | 'A'..='Z' | '_' => self.handle_identifier()?,
             // Other
             ' ' | '\r' | '\t' | '\n' => self.clear_buffer(),
-            x => return Err(SolisError::UnrecognizedCharacter(x)),
+            x => {
+                return Err(SolisError::UnrecognizedCharacter {
+                    character: x,
+                    span: self.span(),
+                })
+            }
         }
 
         Ok(())
@@ -136,24 +240,22 @@ impl<'a> Lexer<'a> {
 
     fn handle_slash(&mut self) -> Result<()> {
         match self.peeked {
-            Some(peeked) if peeked == '/' => {
-                for x in self.by_ref() {
-                    if x == '\n' {
-                        break;
-                    }
+            Some('/') => {
+                while self.peeked.is_some_and(|peeked| peeked != '\n') {
+                    self.next();
                 }
-                self.clear_buffer();
+                self.add_comment();
             }
-            Some(peeked) if peeked == '*' => {
+            Some('*') => {
                 while let Some(x) = self.next() {
                     if (x == '*') && (self.peeked == Some('/')) {
                         self.next();
                         break;
                     }
                 }
-                self.clear_buffer();
+                self.add_comment();
             }
-            Some(peeked) if peeked == '=' => {
+            Some('=') => {
                 self.next();
                 self.add_token(SlashEqual, None)
             }
@@ -162,39 +264,331 @@ impl<'a> Lexer<'a> {
         Ok(())
     }
 
-    fn handle_string(&mut self) -> Result<()> {
-        let mut terminated = false;
-        while let Some(peeked) = self.peeked {
+    fn add_comment(&mut self) {
+        self.comments.push(self.span());
+        self.clear_buffer();
+    }
+
+    fn handle_dot(&mut self) {
+        if self.peeked != Some('.') {
+            return self.add_token(Dot, None);
+        }
+
+        self.next();
+        if self.peeked == Some('.') {
             self.next();
-            if peeked == '"' {
-                terminated = true;
-                break;
+            return self.add_token(Ellipsis, None);
+        }
+
+        // `..` is not an operator, so it lexes as two separate dots.
+        let span = self.span();
+        for offset in 0..2 {
+            let dot = Span::new(
+                span.start + offset,
+                span.start + offset + 1,
+                span.line,
+                span.column + offset,
+            );
+            self.result
+                .push(Token::new(Dot, String::from("."), None, dot));
+        }
+        self.clear_buffer();
+    }
+
+    /// Lexes a string literal, or the rest of one after the `}` of an interpolation. `end`
+    /// is the type of the token if no further `${` follows.
+    fn handle_string(&mut self, end: TokenType) -> Result<()> {
+        let mut value = String::new();
+        let mut error = None;
+        loop {
+            match self.next() {
+                Some('"') => break,
+                Some('$') if self.peeked == Some('{') => {
+                    self.next();
+                    self.interpolations.push((self.span(), 0));
+                    if let Some(err) = error {
+                        return Err(err);
+                    }
+                    self.add_token(Interpolation, Some(ValueWrapper::Str(value)));
+                    return Ok(());
+                }
+                Some('\\') => match self.escape() {
+                    Ok(escaped) => value.push(escaped),
+                    // Keep going to the closing quote, so the rest of the string isn't
+                    // lexed as code.
+                    Err(err) => {
+                        error.get_or_insert(err);
+                    }
+                },
+                Some(c) => value.push(c),
+                None => return Err(SolisError::UnterminatedString { span: self.span() }),
+            }
+        }
+        if let Some(err) = error {
+            return Err(err);
+        }
+        self.add_token(end, Some(ValueWrapper::Str(value)));
+        Ok(())
+    }
+
+    /// Reads the escape sequence following a `\`, returning the character it stands for.
+    fn escape(&mut self) -> Result<char> {
+        let start = Span::new(
+            self.offset - 1,
+            self.offset,
+            self.curr_line,
+            self.curr_column - 1,
+        );
+        let escaped = match self.peeked {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('"') => '"',
+            // So that a `${` can be written without starting an interpolation.
+            Some('$') => '$',
+            Some('u') => {
+                self.next();
+                return self.unicode_escape(start);
+            }
+            // Leave a quote or the end of the line to close or report the string.
+            Some('\n') | None => return Err(self.invalid_escape(start)),
+            Some(_) => {
+                self.next();
+                return Err(self.invalid_escape(start));
             }
+        };
+        self.next();
+        Ok(escaped)
+    }
+
+    /// Reads the `{...}` of a `\u{...}` escape, holding up to six hex digits.
+    fn unicode_escape(&mut self, start: Span) -> Result<char> {
+        if self.peeked != Some('{') {
+            return Err(self.invalid_escape(start));
+        }
+        self.next();
+
+        let mut digits = String::new();
+        while let Some(digit) = self.peeked.filter(char::is_ascii_hexdigit) {
+            digits.push(digit);
+            self.next();
+        }
+        if self.peeked != Some('}') {
+            return Err(self.invalid_escape(start));
         }
-        if !terminated {
-            return Err(SolisError::UnterminatedString);
+        self.next();
+
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .filter(|_| digits.len() <= 6)
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.invalid_escape(start))
+    }
+
+    /// An error for the escape sequence running from `start` to the current character.
+    fn invalid_escape(&self, start: Span) -> SolisError {
+        let len = self.offset - start.start;
+        SolisError::InvalidEscape {
+            escape: self.buffer[self.buffer.len() - len..].to_string(),
+            span: Span {
+                end: self.offset,
+                ..start
+            },
+        }
+    }
+
+    /// A raw string, `r"..."`, which has no escape sequences.
+    fn handle_raw_string(&mut self) -> Result<()> {
+        self.next();
+        loop {
+            match self.next() {
+                Some('"') => break,
+                Some(_) => (),
+                None => return Err(SolisError::UnterminatedString { span: self.span() }),
+            }
         }
-        self.buffer = self.buffer[1..self.buffer.len() - 1].to_string();
-        self.add_token(StringLiteral, Some(ValueWrapper::Str(self.buffer.clone())));
+        let value = self.buffer[2..self.buffer.len() - 1].to_string();
+        self.add_token(StringLiteral, Some(ValueWrapper::Str(value)));
         Ok(())
     }
 
     fn handle_number(&mut self) -> Result<()> {
-        while let Some(peeked) = self.peeked {
-            if !matches!(peeked, '0'..='9' | '.') {
-                break;
+        // The first error found, reported once the whole literal has been read.
+        let mut error = None;
+        // Whether there's a fraction or an exponent, which makes the number a float.
+        let mut float = false;
+        let radix = match (self.current, self.peeked) {
+            (Some('0'), Some('x')) => 16,
+            (Some('0'), Some('o')) => 8,
+            (Some('0'), Some('b')) => 2,
+            _ => 10,
+        };
+
+        if radix == 10 {
+            self.digits(10, &mut error);
+            // A `.` only starts a fraction when a digit follows, so `1.abs` is a property.
+            if self.peeked == Some('.') && self.peek_second().is_some_and(|c| c.is_ascii_digit()) {
+                self.next();
+                float = true;
+                self.digits(10, &mut error);
+                if self.peeked == Some('.')
+                    && self.peek_second().is_some_and(|c| c.is_ascii_digit())
+                {
+                    self.next();
+                    error.get_or_insert((
+                        self.char_span(),
+                        String::from("a number can only have one decimal point"),
+                    ));
+                    self.digits(10, &mut error);
+                }
             }
+            if matches!(self.peeked, Some('e' | 'E')) {
+                self.next();
+                float = true;
+                let exponent = self.char_span();
+                if matches!(self.peeked, Some('+' | '-')) {
+                    self.next();
+                }
+                if self.digits(10, &mut error) == 0 {
+                    error.get_or_insert((
+                        Span {
+                            end: self.offset,
+                            ..exponent
+                        },
+                        String::from("expected digits after the exponent"),
+                    ));
+                }
+            }
+        } else {
             self.next();
+            let prefix = self.span();
+            if self.digits(radix, &mut error) == 0 {
+                error.get_or_insert((
+                    prefix,
+                    format!(
+                        "expected {} digits after `{}`",
+                        radix_name(radix),
+                        self.buffer
+                    ),
+                ));
+            }
         }
-        self.add_token(
-            NumberLiteral,
-            Some(ValueWrapper::Num(self.buffer.parse::<f64>().map_err(
-                |_| SolisError::InvalidNumber(self.buffer.clone()),
-            )?)),
-        );
+
+        // `n` makes a big integer and `d` a decimal.
+        let mut suffix = None;
+        if self
+            .peeked
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            self.next();
+            let span = self.char_span();
+            let start = self.buffer.len() - 1;
+            while self
+                .peeked
+                .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                self.next();
+            }
+            let span = Span {
+                end: self.offset,
+                ..span
+            };
+            match &self.buffer[start..] {
+                "n" if !float => suffix = Some('n'),
+                "d" if radix == 10 => suffix = Some('d'),
+                text => {
+                    let reason = match text {
+                        "n" => String::from("a big integer can't have a fraction or an exponent"),
+                        "d" => format!("a decimal can't be written in {}", radix_name(radix)),
+                        _ => format!("invalid suffix `{}`", text),
+                    };
+                    error.get_or_insert((span, reason));
+                }
+            }
+        }
+
+        if let Some((span, reason)) = error {
+            return Err(SolisError::InvalidNumber {
+                lexeme: self.buffer.clone(),
+                reason,
+                span,
+            });
+        }
+
+        let end = self.buffer.len() - suffix.map_or(0, |_| 1);
+        let digits = self.buffer[..end].replace('_', "");
+        let digits = if radix == 10 { &digits } else { &digits[2..] };
+        let value = match suffix {
+            Some('n') => {
+                num_bigint::BigInt::parse_bytes(digits.as_bytes(), radix).map(ValueWrapper::BigInt)
+            }
+            Some(_) if digits.contains(['e', 'E']) => Decimal::from_scientific(digits)
+                .ok()
+                .map(ValueWrapper::Decimal),
+            Some(_) => Decimal::from_str_exact(digits)
+                .ok()
+                .map(ValueWrapper::Decimal),
+            None if float => digits
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite())
+                .map(ValueWrapper::Num),
+            None => i64::from_str_radix(digits, radix)
+                .ok()
+                .map(ValueWrapper::Int),
+        };
+        let Some(value) = value else {
+            let reason = match suffix {
+                Some(_) => "the number doesn't fit in a decimal",
+                None if float => "the number is too large for a float",
+                None => {
+                    "the integer doesn't fit in 64 bits, add an `n` suffix to make it a big integer"
+                }
+            };
+            return Err(SolisError::InvalidNumber {
+                lexeme: self.buffer.clone(),
+                reason: String::from(reason),
+                span: self.span(),
+            });
+        };
+        self.add_token(NumberLiteral, Some(value));
         Ok(())
     }
 
+    /// Reads digits of `radix` along with the `_`s separating them, returning how many
+    /// digits there were. Decimal digits outside of `radix` are read as well, so that
+    /// they're reported instead of lexing as another number.
+    fn digits(&mut self, radix: u32, error: &mut Option<(Span, String)>) -> usize {
+        let mut count = 0;
+        let mut last = self.current;
+        let mut underscore = None;
+        while let Some(c) = self
+            .peeked
+            .filter(|&c| c == '_' || c.is_digit(radix.max(10)))
+        {
+            self.next();
+            let span = self.char_span();
+            if c == '_' {
+                underscore = Some(span);
+                if !last.is_some_and(|last| last.is_digit(radix.max(10))) {
+                    error.get_or_insert((span, String::from("`_` can only separate digits")));
+                }
+            } else if !c.is_digit(radix) {
+                let reason = format!("`{}` isn't a valid {} digit", c, radix_name(radix));
+                error.get_or_insert((span, reason));
+            } else {
+                count += 1;
+            }
+            last = Some(c);
+        }
+        if let (Some('_'), Some(span)) = (last, underscore) {
+            error.get_or_insert((span, String::from("`_` can only separate digits")));
+        }
+        count
+    }
+
     fn handle_identifier(&mut self) -> Result<()> {
         while let Some(peeked) = self.peeked {
             if !matches!(peeked, 'a'..='z' | 'A'..='Z' | '0'..='9' | '_') {
@@ -215,3 +609,12 @@ impl<'a> Lexer<'a> {
         Ok(())
     }
 }
+
+fn radix_name(radix: u32) -> &'static str {
+    match radix {
+        2 => "binary",
+        8 => "octal",
+        16 => "hexadecimal",
+        _ => "decimal",
+    }
+}
@@ -0,0 +1,124 @@
+use core::*;
+use std::{iter::Peekable, vec::IntoIter};
+
+/// A position in the source, advanced a character at a time the same way the lexer
+/// counts lines and columns.
+struct Cursor {
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Cursor {
+    fn advance(&mut self, text: &str) {
+        for c in text.chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+            self.offset += c.len_utf8();
+        }
+    }
+}
+
+/// Splits the source between `tokens` into trivia and attaches it to them.
+pub(crate) fn attach(source: &str, tokens: Vec<Token>, comments: Vec<Span>) -> Vec<LosslessToken> {
+    let mut cursor = Cursor {
+        offset: 0,
+        line: 1,
+        column: 1,
+    };
+    let mut comments = comments.into_iter().peekable();
+    let mut result: Vec<LosslessToken> = Vec::with_capacity(tokens.len());
+
+    for token in tokens {
+        let mut leading = Vec::new();
+        while cursor.offset < token.span.start {
+            leading.push(next_trivia(
+                source,
+                &mut cursor,
+                token.span.start,
+                &mut comments,
+            ));
+        }
+        if let Some(prev) = result.last_mut() {
+            let line_end = leading
+                .iter()
+                .position(|trivia| trivia.kind == TriviaKind::Newline)
+                .unwrap_or(leading.len());
+            prev.trailing = leading.drain(..line_end).collect();
+        }
+
+        cursor.advance(&source[token.span.start..token.span.end]);
+        result.push(LosslessToken {
+            leading,
+            token,
+            trailing: Vec::new(),
+        });
+    }
+    result
+}
+
+/// Reads the piece of trivia at the cursor, which ends before `end` at the latest.
+fn next_trivia(
+    source: &str,
+    cursor: &mut Cursor,
+    end: usize,
+    comments: &mut Peekable<IntoIter<Span>>,
+) -> Trivia {
+    let rest = &source[cursor.offset..end];
+    let is_whitespace = |c: char| matches!(c, ' ' | '\t' | '\r');
+
+    let (kind, len) = if let Some(comment) = comments.next_if(|c| c.start == cursor.offset) {
+        let len = comment.end - comment.start;
+        match rest.starts_with("//") {
+            // Leave the `\r` of a `\r\n` to the newline.
+            true if rest[..len].ends_with('\r') && rest[len..].starts_with('\n') => {
+                (TriviaKind::LineComment, len - 1)
+            }
+            true => (TriviaKind::LineComment, len),
+            false => (TriviaKind::BlockComment, len),
+        }
+    } else if rest.starts_with("\r\n") {
+        (TriviaKind::Newline, 2)
+    } else if rest.starts_with('\n') {
+        (TriviaKind::Newline, 1)
+    } else {
+        let whitespace = rest.starts_with(is_whitespace);
+        let len = rest
+            .char_indices()
+            .skip(1)
+            .find(|&(i, c)| match whitespace {
+                true => !is_whitespace(c) || rest[i..].starts_with("\r\n"),
+                false => {
+                    is_whitespace(c)
+                        || c == '\n'
+                        || comments
+                            .peek()
+                            .is_some_and(|comment| comment.start == cursor.offset + i)
+                }
+            })
+            .map_or(rest.len(), |(i, _)| i);
+        let kind = match whitespace {
+            true => TriviaKind::Whitespace,
+            false => TriviaKind::Skipped,
+        };
+        (kind, len)
+    };
+
+    let text = &rest[..len];
+    let span = Span::new(
+        cursor.offset,
+        cursor.offset + len,
+        cursor.line,
+        cursor.column,
+    );
+    cursor.advance(text);
+    Trivia {
+        kind,
+        text: text.to_string(),
+        span,
+    }
+}
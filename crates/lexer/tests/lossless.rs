@@ -0,0 +1,44 @@
+use lexer::Lexer;
+
+/// Mostly comments and blank lines, with the odd bits of whitespace a source can have.
+const COMMENTS: &str =
+    "// A file that's mostly comments.\n\n\n/* A block comment\n   over several lines. */\n\
+    \t  \n// Then some code:\nvar a = 1; // trailing\r\nvar b = /* inline */ 2;   \n\n\
+    /** doc-style */\n  \t// indented comment\nprint a + b;\n// the last line has no newline";
+
+/// Joins the text of every token and its trivia, checking that each piece of trivia
+/// points at its own text in the source.
+fn rebuild(source: &str) -> String {
+    let (tokens, errors) = Lexer::lex_lossless(source);
+    assert!(errors.is_empty(), "{:?}", errors);
+    for token in &tokens {
+        for trivia in token.leading.iter().chain(&token.trailing) {
+            assert_eq!(&source[trivia.span.start..trivia.span.end], trivia.text);
+        }
+    }
+    tokens.iter().map(ToString::to_string).collect()
+}
+
+#[test]
+fn tokens_and_trivia_rebuild_the_source() {
+    for source in [
+        include_str!("../../../example.hl"),
+        COMMENTS,
+        "",
+        "\n\n",
+        "// only a comment",
+    ] {
+        assert_eq!(rebuild(source), source);
+    }
+}
+
+#[test]
+fn text_that_fails_to_lex_is_kept() {
+    let source = "var a = 1 # 2;\n";
+    let (tokens, errors) = Lexer::lex_lossless(source);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        tokens.iter().map(ToString::to_string).collect::<String>(),
+        source
+    );
+}
@@ -0,0 +1,166 @@
+use core::{SolisError, TokenType};
+use lexer::Lexer;
+
+/// Lexes `source`, which must be valid, into each token's type, lexeme and literal.
+/// Literals are compared by their debug form, since `==` finds `1` and `1.0` equal.
+fn tokens(source: &str) -> Vec<(TokenType, String, String)> {
+    let (tokens, errors) = Lexer::lex(source);
+    assert!(errors.is_empty(), "{}: {:?}", source, errors);
+    tokens
+        .into_iter()
+        .filter(|token| token.ty != TokenType::EOF)
+        .map(|token| {
+            let literal = token.literal.map_or(String::new(), |l| format!("{:?}", l));
+            (token.ty, token.lexeme, literal)
+        })
+        .collect()
+}
+
+/// The literal of the single number `source` lexes to.
+fn number(source: &str) -> String {
+    match &tokens(source)[..] {
+        [(TokenType::NumberLiteral, lexeme, literal)] if lexeme == source => literal.clone(),
+        tokens => panic!("{} lexed to {:?}", source, tokens),
+    }
+}
+
+/// The reason `source` isn't a valid number, along with the text the error points at.
+fn error(source: &str) -> (String, &str) {
+    let (_, errors) = Lexer::lex(source);
+    match &errors[..] {
+        [SolisError::InvalidNumber {
+            lexeme,
+            reason,
+            span,
+        }] if lexeme == source => (reason.clone(), &source[span.start..span.end]),
+        errors => panic!("{} gave {:?}", source, errors),
+    }
+}
+
+#[test]
+fn radix_prefixes() {
+    assert_eq!(number("0x1F"), "Int(31)");
+    assert_eq!(number("0xff"), "Int(255)");
+    assert_eq!(number("0b101"), "Int(5)");
+    assert_eq!(number("0o17"), "Int(15)");
+    assert_eq!(number("0x1d"), "Int(29)");
+    assert_eq!(number("0x7fff_ffff_ffff_ffff"), "Int(9223372036854775807)");
+    assert_eq!(number("0xffn"), "BigInt(255)");
+}
+
+#[test]
+fn digit_separators() {
+    assert_eq!(number("1_000_000"), "Int(1000000)");
+    assert_eq!(number("0b1010_1010"), "Int(170)");
+    assert_eq!(number("1_0.2_5"), "Num(10.25)");
+    assert_eq!(number("1_0e1_0"), "Num(100000000000.0)");
+    assert_eq!(number("1_000n"), "BigInt(1000)");
+}
+
+#[test]
+fn exponents() {
+    assert_eq!(number("1e3"), "Num(1000.0)");
+    assert_eq!(number("1E3"), "Num(1000.0)");
+    assert_eq!(number("1.5e-2"), "Num(0.015)");
+    assert_eq!(number("2e+2"), "Num(200.0)");
+    assert_eq!(number("1e2d"), "Decimal(100)");
+    assert_eq!(number("2.5e-3d"), "Decimal(0.0025)");
+}
+
+#[test]
+fn suffixes() {
+    assert_eq!(number("12n"), "BigInt(12)");
+    assert_eq!(
+        number("123456789012345678901234567890n"),
+        "BigInt(123456789012345678901234567890)"
+    );
+    assert_eq!(number("19.99d"), "Decimal(19.99)");
+    assert_eq!(number("7d"), "Decimal(7)");
+}
+
+#[test]
+fn integers_and_floats() {
+    assert_eq!(number("0"), "Int(0)");
+    assert_eq!(number("9223372036854775807"), "Int(9223372036854775807)");
+    assert_eq!(number("1.5"), "Num(1.5)");
+    assert_eq!(number("1.0"), "Num(1.0)");
+}
+
+#[test]
+fn a_dot_without_digits_is_a_property() {
+    use TokenType::*;
+    let token = |ty, lexeme: &str, literal: &str| (ty, lexeme.to_string(), literal.to_string());
+    assert_eq!(
+        tokens("1.abs"),
+        [
+            token(NumberLiteral, "1", "Int(1)"),
+            token(Dot, ".", ""),
+            token(Identifier, "abs", ""),
+        ]
+    );
+    assert_eq!(
+        tokens("1.5.abs"),
+        [
+            token(NumberLiteral, "1.5", "Num(1.5)"),
+            token(Dot, ".", ""),
+            token(Identifier, "abs", ""),
+        ]
+    );
+    assert_eq!(
+        tokens("1."),
+        [token(NumberLiteral, "1", "Int(1)"), token(Dot, ".", "")]
+    );
+}
+
+#[test]
+fn numbers_too_large() {
+    let too_large = |source| {
+        let (reason, span) = error(source);
+        assert_eq!(span, source);
+        reason
+    };
+    assert_eq!(
+        too_large("9223372036854775808"),
+        "the integer doesn't fit in 64 bits, add an `n` suffix to make it a big integer"
+    );
+    assert_eq!(
+        too_large("0x8000_0000_0000_0000"),
+        "the integer doesn't fit in 64 bits, add an `n` suffix to make it a big integer"
+    );
+    assert_eq!(too_large("1e999"), "the number is too large for a float");
+    assert_eq!(too_large("1.5e400"), "the number is too large for a float");
+    assert_eq!(
+        too_large("99999999999999999999999999999d"),
+        "the number doesn't fit in a decimal"
+    );
+}
+
+#[test]
+fn malformed_numbers() {
+    let cases = [
+        ("0x", "expected hexadecimal digits after `0x`", "0x"),
+        ("0b", "expected binary digits after `0b`", "0b"),
+        ("0b102", "`2` isn't a valid binary digit", "2"),
+        ("0o78", "`8` isn't a valid octal digit", "8"),
+        ("1__0", "`_` can only separate digits", "_"),
+        ("1_", "`_` can only separate digits", "_"),
+        ("1e", "expected digits after the exponent", "e"),
+        ("1e+", "expected digits after the exponent", "e+"),
+        ("1.2.3", "a number can only have one decimal point", "."),
+        (
+            "1.5n",
+            "a big integer can't have a fraction or an exponent",
+            "n",
+        ),
+        (
+            "1e3n",
+            "a big integer can't have a fraction or an exponent",
+            "n",
+        ),
+        ("0b1d", "a decimal can't be written in binary", "d"),
+        ("12abc", "invalid suffix `abc`", "abc"),
+    ];
+    for (source, reason, span) in cases {
+        assert_eq!(error(source), (reason.to_string(), span), "{}", source);
+    }
+}
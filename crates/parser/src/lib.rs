@@ -1,6 +1,6 @@
 #![allow(unused_assignments)]
 use core::{TokenType::*, *};
-use interpreter::functions::SolisFunction;
+use std::rc::Rc;
 
 pub type ExprRes = Result<Box<Expr>>;
 pub type StmtRes = Result<Box<Stmt>>;
@@ -19,11 +19,19 @@ macro_rules! stmt {
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    errors: Vec<SolisError>,
+    /// How many blocks deep the parser currently is.
+    depth: usize,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, current: 0 }
+        Self {
+            tokens,
+            current: 0,
+            errors: Vec::new(),
+            depth: 0,
+        }
     }
 
     // - Utils
@@ -54,6 +62,13 @@ impl Parser {
         &self.peek().ty == token_type
     }
 
+    fn check_next(&self, token_type: &TokenType) -> bool {
+        match self.tokens.get(self.current + 1) {
+            Some(token) => &token.ty == token_type,
+            None => false,
+        }
+    }
+
     fn advance(&mut self) -> Token {
         if !self.is_at_end() {
             self.current += 1
@@ -85,8 +100,18 @@ impl Parser {
         })
     }
 
+    /// The span from `start` up to and including the previously consumed token.
+    fn span_from(&self, start: Span) -> Span {
+        start.to(self.previous().span)
+    }
+
+    /// Skips tokens until the start of the next statement, so that parsing can resume
+    /// after a syntax error.
     fn synchronize(&mut self) {
-        self.advance();
+        // A `}` closing an enclosing block is left for that block to consume.
+        if self.depth == 0 || !self.check(&RightBrace) {
+            self.advance();
+        }
 
         while !self.is_at_end() {
             if self.previous().ty == Semicolon {
@@ -94,7 +119,8 @@ impl Parser {
             };
 
             match self.peek().ty {
-                Class | Fn | Var | For | If | While | Print | Return => return,
+                Class | Fn | Var | For | If | While | Print | Return | Break | Continue
+                | RightBrace => return,
                 _ => (),
             }
 
@@ -104,36 +130,87 @@ impl Parser {
 
     // - End: Utils
 
-    pub fn parse(&mut self) -> Result<Vec<Box<Stmt>>> {
+    /// Parses the whole token stream, recovering from syntax errors at statement
+    /// boundaries. Returns every statement that parsed successfully alongside all the
+    /// errors encountered; the statements should only be run if there are no errors.
+    pub fn parse(&mut self) -> (Vec<Box<Stmt>>, Vec<SolisError>) {
         let mut statements = Vec::new();
         while !self.is_at_end() {
-            statements.push(self.declaration()?)
+            if let Some(statement) = self.recovering_declaration() {
+                statements.push(statement);
+            }
+        }
+        (statements, std::mem::take(&mut self.errors))
+    }
+
+    fn recovering_declaration(&mut self) -> Option<Box<Stmt>> {
+        match self.declaration() {
+            Ok(statement) => Some(statement),
+            Err(err) => {
+                self.errors.push(err);
+                self.synchronize();
+                None
+            }
         }
-        Ok(statements)
     }
 
     fn declaration(&mut self) -> StmtRes {
+        if self.next_match_s(&Class) {
+            return self.class_declaration();
+        }
         if self.next_match_s(&Var) {
             return self.var_declaration();
         }
         self.statement()
     }
 
+    fn class_declaration(&mut self) -> StmtRes {
+        let start = self.previous().span;
+        let name = self.consume(Identifier, "class name")?;
+
+        let mut superclass = None;
+        if self.next_match_s(&Less) {
+            self.consume(Identifier, "superclass name")?;
+            let name = self.previous();
+            let span = name.span;
+            superclass = Some(Expr::variable(name, None, span));
+        }
+
+        self.consume(LeftBrace, "'{' before class body")?;
+        let mut methods = Vec::new();
+        while !self.check(&RightBrace) && !self.is_at_end() {
+            methods.push(*self.function("method")?);
+        }
+        self.consume(RightBrace, "'}' after class body")?;
+
+        Ok(Stmt::class(
+            name,
+            superclass,
+            methods,
+            self.span_from(start),
+        ))
+    }
+
     fn var_declaration(&mut self) -> StmtRes {
+        let start = self.previous().span;
         let name = self.consume(Identifier, "variable name")?;
         let mut inititalizer = None;
         if self.next_match_s(&Equal) {
             inititalizer = Some(self.expression()?);
         }
-        self.consume(Semicolon, "`;` after expression.")?;
+        self.consume(Semicolon, "`;` after expression")?;
 
-        Ok(Stmt::var(name, inititalizer))
+        Ok(Stmt::var(name, inititalizer, self.span_from(start)))
     }
 
     fn statement(&mut self) -> StmtRes {
+        // Only `fn name(...)` declares a function; `fn (...)` starts a lambda expression.
+        if self.check(&Fn) && self.check_next(&Identifier) {
+            self.advance();
+            return self.function("function");
+        }
         stmt!(self, {
             Return => return_stmt,
-            Fn => function; "function",
             Break => break_stmt,
             Continue => continue_stmt,
             For => for_stmt,
@@ -147,53 +224,110 @@ impl Parser {
     }
 
     fn continue_stmt(&mut self) -> StmtRes {
-        self.consume(Semicolon, "Expected ';' after continue statment.")?;
-        Ok(Stmt::continuestmt(self.previous()))
+        let location = self.previous();
+        self.consume(Semicolon, "';' after continue statement")?;
+        Ok(Stmt::continuestmt(
+            location.clone(),
+            self.span_from(location.span),
+        ))
     }
 
     fn break_stmt(&mut self) -> StmtRes {
-        self.consume(Semicolon, "Expected ';' after break statment.")?;
-        Ok(Stmt::breakstmt(self.previous()))
+        let location = self.previous();
+        self.consume(Semicolon, "';' after break statement")?;
+        Ok(Stmt::breakstmt(
+            location.clone(),
+            self.span_from(location.span),
+        ))
     }
 
     fn return_stmt(&mut self) -> StmtRes {
         let keyword = self.previous();
-        let mut value = Expr::literal(ValueWrapper::Nil);
+        let mut value = None;
         if !self.check(&Semicolon) {
-            value = self.expression()?;
+            value = Some(self.expression()?);
         }
-        self.consume(Semicolon, "Expected ';' after return value.")?;
-        Ok(Stmt::returnstmt(keyword, value))
+        self.consume(Semicolon, "';' after return value")?;
+        let span = self.span_from(keyword.span);
+        Ok(Stmt::returnstmt(keyword, value, span))
     }
 
     fn function(&mut self, kind: &str) -> StmtRes {
-        let name = self.consume(Identifier, format!("Expected {} name.", kind))?;
-        self.consume(LeftParen, format!("Expected '(' after {} name.", kind))?;
-        let mut parameters = Vec::new();
-        if !self.check(&RightParen) {
+        // Methods have no leading `fn` keyword, so they start at their name.
+        let start = match self.previous().ty {
+            Fn => self.previous().span,
+            _ => self.peek().span,
+        };
+        let name = self.consume(Identifier, format!("{} name", kind))?;
+        self.consume(LeftParen, format!("'(' after {} name", kind))?;
+        let (params, rest) = self.parameters(&RightParen)?;
+        self.consume(RightParen, String::from("')' after parameters"))?;
+
+        self.consume(LeftBrace, format!("'{{' before {} body", kind))?;
+        let body = self.block_statements()?;
+        let function = FunctionDecl { params, rest, body };
+        Ok(Stmt::function(
+            name,
+            Rc::new(function),
+            self.span_from(start),
+        ))
+    }
+
+    /// Parses a parameter list, and any rest parameter, up to but not including the
+    /// `closing` token.
+    fn parameters(&mut self, closing: &TokenType) -> Result<(Vec<Param>, Option<Token>)> {
+        let mut parameters: Vec<Param> = Vec::new();
+        let mut rest = None;
+        if !self.check(closing) {
             loop {
                 if parameters.len() >= 255 {
                     return Err(SolisError::RuntimeError(
-                        self.peek().line,
+                        self.peek().span,
                         String::from("Can't have more than 255 arguments."),
                     ));
                 }
 
-                parameters.push(self.consume(Identifier, "Expected parameter name.")?);
+                // A rest parameter collects any extra arguments, so it must come last.
+                if self.next_match_s(&Ellipsis) {
+                    rest = Some(self.consume(Identifier, "rest parameter name")?);
+                    if self.check(&Comma) {
+                        return Err(SolisError::RuntimeError(
+                            self.peek().span,
+                            String::from("A rest parameter must be the last parameter."),
+                        ));
+                    }
+                    break;
+                }
+
+                let name = self.consume(Identifier, "parameter name")?;
+                // Defaults are evaluated on each call, so they may refer to earlier
+                // parameters. Once one parameter has a default, all later ones need one.
+                let mut default = None;
+                if self.next_match_s(&Equal) {
+                    default = Some(*self.expression()?);
+                } else if parameters
+                    .last()
+                    .is_some_and(|param| param.default.is_some())
+                {
+                    return Err(SolisError::RuntimeError(
+                        self.previous().span,
+                        String::from(
+                            "Parameters without a default must come before those with one.",
+                        ),
+                    ));
+                }
+                parameters.push(Param { name, default });
                 if !self.next_match_s(&Comma) {
                     break;
                 }
             }
         }
-        self.consume(RightParen, String::from("Expected ')' after parameters."))?;
-
-        self.consume(LeftBrace, format!("Expected '{{' before {} body.", kind))?;
-        let body = self.block()?;
-        Ok(Stmt::function(SolisFunction::new(name, parameters, body)))
+        Ok((parameters, rest))
     }
 
     fn for_stmt(&mut self) -> StmtRes {
-        self.consume(LeftParen, "Expected '(' after 'while'.")?;
+        let start = self.previous().span;
+        self.consume(LeftParen, "'(' after 'for'")?;
         let (mut initializer, mut condition, mut increment) = (None, None, None);
 
         // Initializer
@@ -209,47 +343,52 @@ impl Parser {
         if !self.check(&Semicolon) {
             condition = Some(self.expression()?);
         }
-        self.consume(Semicolon, "Expected ';' after loop condition.")?;
+        self.consume(Semicolon, "';' after loop condition")?;
 
         // Increment
         if !self.check(&RightParen) {
             increment = Some(self.expression()?);
         }
-        self.consume(RightParen, "Expected ')' after 'while'.")?;
+        self.consume(RightParen, "')' after for clauses")?;
 
-        let mut body = self.statement()?;
+        let body = self.statement()?;
+        let span = self.span_from(start);
         let condition = match condition {
             Some(t) => t,
-            None => Expr::literal(ValueWrapper::Bool(true)),
+            None => Expr::literal(ValueWrapper::Bool(true), start),
         };
 
-        if let Some(increment) = increment {
-            body = Stmt::block(vec![*body, *Stmt::expression(increment)])
-        }
-
-        body = Stmt::whilestmt(condition, body);
+        // The increment stays out of the body so `continue` still runs it.
+        let mut body = Stmt::whilestmt(condition, body, increment, span);
 
         if let Some(initializer) = initializer {
-            body = Stmt::block(vec![*initializer, *body])
+            body = Stmt::block(vec![*initializer, *body], span)
         }
 
         Ok(body)
     }
 
     fn while_stmt(&mut self) -> StmtRes {
-        self.consume(LeftParen, "Expected '(' after 'while'.")?;
+        let start = self.previous().span;
+        self.consume(LeftParen, "'(' after 'while'")?;
         let condition = self.expression()?;
-        self.consume(RightParen, "Expected ')' after 'while'.")?;
+        self.consume(RightParen, "')' after 'while'")?;
 
         let body = self.statement()?;
 
-        Ok(Stmt::whilestmt(condition, body))
+        Ok(Stmt::whilestmt(
+            condition,
+            body,
+            None,
+            self.span_from(start),
+        ))
     }
 
     fn if_stmt(&mut self) -> StmtRes {
-        self.consume(LeftParen, "Expected '(' after 'if'.")?;
+        let start = self.previous().span;
+        self.consume(LeftParen, "'(' after 'if'")?;
         let condition = self.expression()?;
-        self.consume(RightParen, "Expected ')' after if condition.")?;
+        self.consume(RightParen, "')' after if condition")?;
 
         let then_branch = self.statement()?;
         let else_branch = match self.next_match_s(&Else) {
@@ -257,31 +396,48 @@ impl Parser {
             false => None,
         };
 
-        Ok(Stmt::ifstmt(condition, then_branch, else_branch))
+        Ok(Stmt::ifstmt(
+            condition,
+            then_branch,
+            else_branch,
+            self.span_from(start),
+        ))
     }
 
     fn print_stmt(&mut self) -> StmtRes {
+        let start = self.previous().span;
         let expr = self.expression()?;
-        self.consume(Semicolon, "Expected `;` after expression.")?;
+        self.consume(Semicolon, "`;` after expression")?;
 
-        Ok(Stmt::print(expr))
+        Ok(Stmt::print(expr, self.span_from(start)))
     }
 
     fn block(&mut self) -> StmtRes {
+        let start = self.previous().span;
+        let statements = self.block_statements()?;
+        Ok(Stmt::block(statements, self.span_from(start)))
+    }
+
+    fn block_statements(&mut self) -> Result<Vec<Stmt>> {
         let mut statements = Vec::new();
-        while !self.check(&RightBrace) {
-            statements.push(*self.declaration()?);
+        self.depth += 1;
+        while !self.check(&RightBrace) && !self.is_at_end() {
+            if let Some(statement) = self.recovering_declaration() {
+                statements.push(*statement);
+            }
         }
+        self.depth -= 1;
 
-        self.consume(RightBrace, "Expected '}' after block.")?;
-        Ok(Stmt::block(statements))
+        self.consume(RightBrace, "'}' after block")?;
+        Ok(statements)
     }
 
     fn expression_stmt(&mut self) -> StmtRes {
         let expr = self.expression()?;
-        self.consume(Semicolon, "Expected `;` after expression.")?;
+        self.consume(Semicolon, "`;` after expression")?;
 
-        Ok(Stmt::expression(expr))
+        let span = self.span_from(expr.span());
+        Ok(Stmt::expression(expr, span))
     }
 
     fn expression(&mut self) -> ExprRes {
@@ -291,29 +447,56 @@ impl Parser {
     fn assignment(&mut self) -> ExprRes {
         let expr = self.or()?;
 
-        if self.next_match_m(&[PlusEqual, MinusEqual, SlashEqual, StarEqual]) {
+        if self.next_match_m(&[
+            PlusEqual,
+            MinusEqual,
+            SlashEqual,
+            StarEqual,
+            PercentEqual,
+            TildeSlashEqual,
+        ]) {
             let op = self.previous();
             let value = self.assignment()?;
+            let span = expr.span().to(value.span());
+            let operator = Token::new(
+                match op.ty {
+                    PlusEqual => Plus,
+                    MinusEqual => Minus,
+                    SlashEqual => Slash,
+                    StarEqual => Star,
+                    PercentEqual => Percent,
+                    TildeSlashEqual => TildeSlash,
+                    _ => panic!("Unreachable"),
+                },
+                op.lexeme.clone(),
+                op.literal.clone(),
+                op.span,
+            );
 
             return match *expr {
-                Expr::Variable { name } => Ok(Expr::assign(
+                Expr::Variable { name, .. } => Ok(Expr::assign(
                     name.clone(),
                     Expr::binary(
-                        Expr::variable(name),
-                        Token::new(
-                            match op.ty {
-                                PlusEqual => Plus,
-                                MinusEqual => Minus,
-                                SlashEqual => Slash,
-                                StarEqual => Star,
-                                _ => panic!("Unreachable"),
-                            },
-                            op.lexeme,
-                            op.literal,
-                            op.line,
-                        ),
+                        Expr::variable(name.clone(), None, name.span),
+                        operator,
                         value,
+                        span,
                     ),
+                    None,
+                    span,
+                )),
+                Expr::Index {
+                    object,
+                    bracket,
+                    index,
+                    ..
+                } => Ok(Expr::setindex(
+                    object,
+                    bracket,
+                    index,
+                    value,
+                    Some(operator),
+                    span,
                 )),
                 _ => Err(SolisError::InvalidAssignmentTarget { token: op }),
             };
@@ -322,9 +505,17 @@ impl Parser {
         if self.next_match_s(&Equal) {
             let op = self.previous();
             let value = self.assignment()?;
+            let span = expr.span().to(value.span());
 
             return match *expr {
-                Expr::Variable { name } => Ok(Expr::assign(name, value)),
+                Expr::Variable { name, .. } => Ok(Expr::assign(name, value, None, span)),
+                Expr::Get { object, name, .. } => Ok(Expr::set(object, name, value, span)),
+                Expr::Index {
+                    object,
+                    bracket,
+                    index,
+                    ..
+                } => Ok(Expr::setindex(object, bracket, index, value, None, span)),
                 _ => Err(SolisError::InvalidAssignmentTarget { token: op }),
             };
         }
@@ -338,7 +529,8 @@ impl Parser {
         while self.next_match_s(&Or) {
             let operator = self.previous();
             let right = self.and()?;
-            expr = Expr::logical(expr, operator, right)
+            let span = expr.span().to(right.span());
+            expr = Expr::logical(expr, operator, right, span)
         }
 
         Ok(expr)
@@ -349,7 +541,8 @@ impl Parser {
         while self.next_match_s(&And) {
             let operator = self.previous();
             let right = self.equality()?;
-            expr = Expr::logical(expr, operator, right)
+            let span = expr.span().to(right.span());
+            expr = Expr::logical(expr, operator, right, span)
         }
         Ok(expr)
     }
@@ -360,7 +553,8 @@ impl Parser {
         while self.next_match_m(&[BangEqual, EqualEqual]) {
             let operator = self.previous();
             let right = self.comparison()?;
-            expr = Expr::binary(expr, operator, right);
+            let span = expr.span().to(right.span());
+            expr = Expr::binary(expr, operator, right, span);
         }
 
         Ok(expr)
@@ -373,7 +567,8 @@ impl Parser {
             let operator = self.previous();
             let right = self.term()?;
 
-            expr = Expr::binary(expr, operator, right);
+            let span = expr.span().to(right.span());
+            expr = Expr::binary(expr, operator, right, span);
         }
 
         Ok(expr)
@@ -386,7 +581,8 @@ impl Parser {
             let operator = self.previous();
             let right = self.factor()?;
 
-            expr = Expr::binary(expr, operator, right);
+            let span = expr.span().to(right.span());
+            expr = Expr::binary(expr, operator, right, span);
         }
 
         Ok(expr)
@@ -395,11 +591,12 @@ impl Parser {
     fn factor(&mut self) -> ExprRes {
         let mut expr = self.unary()?;
 
-        while self.next_match_m(&[Slash, Star]) {
+        while self.next_match_m(&[Slash, Star, Percent, TildeSlash]) {
             let operator = self.previous();
             let right = self.unary()?;
 
-            expr = Expr::binary(expr, operator, right)
+            let span = expr.span().to(right.span());
+            expr = Expr::binary(expr, operator, right, span)
         }
 
         Ok(expr)
@@ -409,8 +606,9 @@ impl Parser {
         if self.next_match_m(&[Bang, Minus]) {
             let operator = self.previous();
             let right = self.unary()?;
+            let span = operator.span.to(right.span());
 
-            return Ok(Expr::unary(operator, right));
+            return Ok(Expr::unary(operator, right, span));
         }
 
         self.call()
@@ -421,6 +619,16 @@ impl Parser {
         loop {
             if self.next_match_s(&LeftParen) {
                 expr = self.finish_call(expr)?;
+            } else if self.next_match_s(&Dot) {
+                let name = self.consume(Identifier, "property name after '.'")?;
+                let span = expr.span().to(name.span);
+                expr = Expr::get(expr, name, span);
+            } else if self.next_match_s(&LeftBracket) {
+                let bracket = self.previous();
+                let index = self.expression()?;
+                self.consume(RightBracket, "']' after index")?;
+                let span = self.span_from(expr.span());
+                expr = Expr::index(expr, bracket, index, span);
             } else {
                 break;
             }
@@ -431,52 +639,133 @@ impl Parser {
 
     fn finish_call(&mut self, callee: Box<Expr>) -> ExprRes {
         let mut args = Vec::new();
+        let mut named = Vec::new();
         if !self.check(&RightParen) {
             loop {
-                if args.len() >= 255 {
+                if args.len() + named.len() >= 255 {
                     return Err(SolisError::RuntimeError(
-                        self.peek().line,
+                        self.peek().span,
                         String::from("Can't have more than 255 arguments."),
                     ));
                 }
-                args.push(self.expression()?);
+
+                if self.check(&Identifier) && self.check_next(&Colon) {
+                    let name = self.advance();
+                    self.advance();
+                    named.push((name, self.expression()?));
+                } else if named.is_empty() {
+                    args.push(self.expression()?);
+                } else {
+                    return Err(SolisError::RuntimeError(
+                        self.peek().span,
+                        String::from("Positional arguments must come before named arguments."),
+                    ));
+                }
                 if !self.next_match_s(&Comma) {
                     break;
                 }
             }
         }
 
-        let paren = self.consume(RightParen, "Expected ')' after arguments.")?;
-        Ok(Expr::call(callee, paren, args))
+        let paren = self.consume(RightParen, "')' after arguments")?;
+        let span = callee.span().to(paren.span);
+        Ok(Expr::call(callee, paren, args, named, span))
     }
 
     fn primary(&mut self) -> ExprRes {
-        if self.next_match_s(&BoolLiteral) {
-            match self.previous().literal {
-                Some(t) => return Ok(Expr::literal(t)),
-                _ => return Err(SolisError::MissingLiteral(self.previous().ty)),
-            }
+        if self.next_match_s(&Interpolation) {
+            return self.interpolation();
         }
 
-        if self.next_match_s(&NilLiteral) {
-            return Ok(Expr::literal(ValueWrapper::Nil));
+        if self.next_match_m(&[BoolLiteral, NilLiteral, StringLiteral, NumberLiteral]) {
+            let token = self.previous();
+            return match token.literal {
+                Some(t) => Ok(Expr::literal(t, token.span)),
+                _ => Err(SolisError::MissingLiteral { token }),
+            };
         }
 
-        if self.next_match_m(&[StringLiteral, NumberLiteral]) {
-            match self.previous().literal {
-                Some(t) => return Ok(Expr::literal(t)),
-                _ => return Err(SolisError::MissingLiteral(self.previous().ty)),
-            }
+        if self.next_match_s(&This) {
+            let keyword = self.previous();
+            let span = keyword.span;
+            return Ok(Expr::this(keyword, None, span));
         }
+
+        if self.next_match_s(&Super) {
+            let keyword = self.previous();
+            self.consume(Dot, "'.' after 'super'")?;
+            let method = self.consume(Identifier, "superclass method name")?;
+            let span = keyword.span.to(method.span);
+            return Ok(Expr::superexpr(keyword, method, None, span));
+        }
+
         if self.next_match_s(&Identifier) {
-            return Ok(Expr::variable(self.previous()));
+            let name = self.previous();
+            let span = name.span;
+            return Ok(Expr::variable(name, None, span));
+        }
+
+        if self.next_match_s(&Fn) {
+            let keyword = self.previous();
+            self.consume(LeftParen, "'(' after 'fn'")?;
+            let (params, rest) = self.parameters(&RightParen)?;
+            self.consume(RightParen, "')' after parameters")?;
+            self.consume(LeftBrace, "'{' before lambda body")?;
+            let body = self.block_statements()?;
+            let function = FunctionDecl { params, rest, body };
+            let span = self.span_from(keyword.span);
+            return Ok(Expr::lambda(keyword, Rc::new(function), span));
+        }
+
+        // The short form `|x| x * 2` returns the value of its body expression.
+        if self.next_match_s(&Pipe) {
+            let keyword = self.previous();
+            let (params, rest) = self.parameters(&Pipe)?;
+            let pipe = self.consume(Pipe, "'|' after lambda parameters")?;
+            let value = self.expression()?;
+            let span = value.span();
+            let body = vec![*Stmt::returnstmt(pipe, Some(value), span)];
+            let function = FunctionDecl { params, rest, body };
+            let span = self.span_from(keyword.span);
+            return Ok(Expr::lambda(keyword, Rc::new(function), span));
+        }
+
+        if self.next_match_s(&LeftBracket) {
+            let start = self.previous().span;
+            let mut elements = Vec::new();
+            while !self.check(&RightBracket) {
+                elements.push(self.expression()?);
+                if !self.next_match_s(&Comma) {
+                    break;
+                }
+            }
+            self.consume(RightBracket, "']' after list elements")?;
+
+            return Ok(Expr::list(elements, self.span_from(start)));
+        }
+
+        if self.next_match_s(&LeftBrace) {
+            let start = self.previous().span;
+            let mut entries = Vec::new();
+            while !self.check(&RightBrace) {
+                let key = self.expression()?;
+                self.consume(Colon, "':' after map key")?;
+                entries.push((key, self.expression()?));
+                if !self.next_match_s(&Comma) {
+                    break;
+                }
+            }
+            self.consume(RightBrace, "'}' after map entries")?;
+
+            return Ok(Expr::map(entries, self.span_from(start)));
         }
 
         if self.next_match_s(&LeftParen) {
+            let start = self.previous().span;
             let expr = self.expression()?;
-            self.consume(RightParen, "`)`")?;
+            self.consume(RightParen, "')' after expression")?;
 
-            return Ok(Expr::grouping(expr));
+            return Ok(Expr::grouping(expr, self.span_from(start)));
         }
 
         let pk = self.peek();
@@ -485,4 +774,54 @@ impl Parser {
             expected: "expression".into(),
         })
     }
+
+    /// Parses the rest of a string literal after its first `${`, alternating between the
+    /// expressions inside `${...}` and the text around them.
+    fn interpolation(&mut self) -> ExprRes {
+        let start = self.previous().span;
+        let mut parts = Vec::new();
+        let mut segment = self.previous();
+        loop {
+            if segment.literal != Some(ValueWrapper::Str(String::new())) {
+                parts.push(Self::segment(segment)?);
+            }
+            if self.closes_interpolation() {
+                return Err(SolisError::MissingToken {
+                    token: self.peek(),
+                    expected: "expression".into(),
+                });
+            }
+            parts.push(self.expression()?);
+
+            if self.next_match_s(&Interpolation) {
+                segment = self.previous();
+                continue;
+            }
+            if !self.closes_interpolation() {
+                return Err(SolisError::MissingToken {
+                    token: self.peek(),
+                    expected: "'}' after interpolated expression".into(),
+                });
+            }
+            let end = self.advance();
+            if end.literal != Some(ValueWrapper::Str(String::new())) {
+                parts.push(Self::segment(end)?);
+            }
+            return Ok(Expr::interpolation(parts, self.span_from(start)));
+        }
+    }
+
+    /// Whether the next token is the rest of a string, which the lexer starts at the `}`
+    /// closing an interpolated expression.
+    fn closes_interpolation(&self) -> bool {
+        self.check(&InterpolationEnd)
+    }
+
+    /// The text of an interpolated string around its expressions.
+    fn segment(token: Token) -> ExprRes {
+        match token.literal {
+            Some(literal) => Ok(Expr::literal(literal, token.span)),
+            None => Err(SolisError::MissingLiteral { token }),
+        }
+    }
 }
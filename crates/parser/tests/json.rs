@@ -0,0 +1,52 @@
+use core::{ast_from_json, ast_to_json, AstPrinter, Stmt};
+use lexer::Lexer;
+use parser::Parser;
+
+/// Programs using every kind of node and literal.
+const PROGRAMS: &[&str] = &[
+    include_str!("../../../example.hl"),
+    r#"
+var numbers = [1, -2.5, 0x1F, 1e3, 12345678901234567890123n, 19.99d, 1_000];
+var strings = ["plain", "tab\t\"quoted\"", r"raw ${x}", "sum ${1 + 2} and ${ "nested ${3}" }"];
+var m = {"a": true, 2: nil, 3.5: false};
+m["a"] = numbers[0] ~/ 2 % 3;
+numbers[1] += 1;
+fn apply(f, x = 1, ...rest) { return f(x); }
+print apply(|x| x * 2, x: 21);
+print apply(fn (x) { return -x; });
+class Animal {
+  init(name) { this.name = name; }
+  speak() { return this.name; }
+}
+class Dog < Animal {
+  speak() { return super.speak() + "!"; }
+}
+for (var i = 0; i < 3; i = i + 1) { if (i == 1) continue; else if (!i) break; }
+while (false or nil and true) {}
+fn nothing() { return; }
+"#,
+];
+
+#[allow(clippy::vec_box)] // The program as the parser hands it out.
+fn parse(source: &str) -> Vec<Box<Stmt>> {
+    let (tokens, errors) = Lexer::lex(source);
+    assert!(errors.is_empty(), "{:?}", errors);
+    let (statements, errors) = Parser::new(tokens).parse();
+    assert!(errors.is_empty(), "{:?}", errors);
+    statements
+}
+
+#[test]
+fn json_round_trips_keep_the_whole_tree() {
+    for source in PROGRAMS {
+        let statements = parse(source);
+        let json = ast_to_json(&statements).unwrap();
+        let read = ast_from_json(&json).unwrap();
+
+        // The debug form holds every span and literal, along with the exact variant of
+        // each number, which `==` on values wouldn't tell apart.
+        assert_eq!(format!("{:?}", read), format!("{:?}", statements));
+        assert_eq!(AstPrinter::print(&read), AstPrinter::print(&statements));
+        assert_eq!(ast_to_json(&read).unwrap(), json);
+    }
+}
@@ -0,0 +1,310 @@
+use core::*;
+use log::debug;
+use rustc_hash::FxHashMap;
+use std::rc::Rc;
+
+#[derive(Clone, Copy, PartialEq)]
+enum FunctionType {
+    None,
+    Function,
+    Method,
+    Initializer,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ClassType {
+    None,
+    Class,
+    Subclass,
+}
+
+pub struct Resolver {
+    scopes: Vec<FxHashMap<String, bool>>,
+    current_function: FunctionType,
+    current_class: ClassType,
+    in_loop: bool,
+    errors: Vec<SolisError>,
+}
+
+impl Resolver {
+    fn new() -> Self {
+        Self {
+            scopes: Vec::new(),
+            current_function: FunctionType::None,
+            current_class: ClassType::None,
+            in_loop: false,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Binds every variable access in `statements` to the number of scopes between
+    /// the access and its declaration. Accesses left unbound refer to globals. Returns
+    /// every error found, in source order.
+    pub fn resolve(statements: &mut [Box<Stmt>]) -> Vec<SolisError> {
+        let mut slf = Self::new();
+        let start = std::time::Instant::now();
+
+        for statement in statements.iter_mut() {
+            slf.visit_stmt_mut(statement);
+        }
+
+        debug!(
+            "resolved {} statements in {:?}",
+            statements.len(),
+            start.elapsed()
+        );
+        slf.errors
+    }
+
+    // - Utils
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(FxHashMap::default());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: &Token) {
+        let Some(scope) = self.scopes.last_mut() else {
+            return;
+        };
+
+        if scope.contains_key(&name.lexeme) {
+            self.errors.push(SolisError::DuplicateDeclaration {
+                token: name.clone(),
+            });
+        }
+
+        scope.insert(name.lexeme.clone(), false);
+    }
+
+    fn define(&mut self, name: &Token) {
+        self.define_str(&name.lexeme);
+    }
+
+    fn define_str(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), true);
+        }
+    }
+
+    fn check_in_loop(&mut self, location: &Token) {
+        if !self.in_loop {
+            self.errors.push(SolisError::OutsideLoop {
+                token: location.clone(),
+            });
+        }
+    }
+
+    fn resolve_local(&self, name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .position(|scope| scope.contains_key(name))
+    }
+
+    // - End: Utils
+
+    fn resolve_function(&mut self, function: &mut Rc<FunctionDecl>, ty: FunctionType) {
+        let enclosing_function = std::mem::replace(&mut self.current_function, ty);
+        let enclosing_loop = std::mem::replace(&mut self.in_loop, false);
+        let function = Rc::make_mut(function);
+
+        self.begin_scope();
+        // A default only sees the parameters declared before it.
+        for param in &mut function.params {
+            if let Some(default) = &mut param.default {
+                self.visit_expr_mut(default);
+            }
+            self.declare(&param.name);
+            self.define(&param.name);
+        }
+        if let Some(rest) = &function.rest {
+            self.declare(rest);
+            self.define(rest);
+        }
+        for statement in &mut function.body {
+            self.visit_stmt_mut(statement);
+        }
+        self.end_scope();
+
+        self.current_function = enclosing_function;
+        self.in_loop = enclosing_loop;
+    }
+}
+
+impl StmtVisitorMut<()> for Resolver {
+    fn visit_block_mut(&mut self, statements: &mut Vec<Stmt>, _: Span) {
+        self.begin_scope();
+        for statement in statements {
+            self.visit_stmt_mut(statement);
+        }
+        self.end_scope();
+    }
+
+    fn visit_break_stmt_mut(&mut self, location: &mut Token, _: Span) {
+        self.check_in_loop(location)
+    }
+
+    fn visit_continue_stmt_mut(&mut self, location: &mut Token, _: Span) {
+        self.check_in_loop(location)
+    }
+
+    fn visit_var_mut(&mut self, name: &mut Token, inititalizer: &mut Option<Box<Expr>>, _: Span) {
+        self.declare(name);
+        if let Some(inititalizer) = inititalizer {
+            self.visit_expr_mut(inititalizer);
+        }
+        self.define(name);
+    }
+
+    fn visit_while_stmt_mut(
+        &mut self,
+        condition: &mut Box<Expr>,
+        body: &mut Box<Stmt>,
+        increment: &mut Option<Box<Expr>>,
+        _: Span,
+    ) {
+        self.visit_expr_mut(condition);
+
+        let enclosing = std::mem::replace(&mut self.in_loop, true);
+        self.visit_stmt_mut(body);
+        self.in_loop = enclosing;
+        if let Some(increment) = increment {
+            self.visit_expr_mut(increment);
+        }
+    }
+
+    fn visit_function_mut(&mut self, name: &mut Token, function: &mut Rc<FunctionDecl>, _: Span) {
+        self.declare(name);
+        self.define(name);
+        self.resolve_function(function, FunctionType::Function)
+    }
+
+    fn visit_class_mut(
+        &mut self,
+        name: &mut Token,
+        superclass: &mut Option<Box<Expr>>,
+        methods: &mut Vec<Stmt>,
+        _: Span,
+    ) {
+        let enclosing_class = std::mem::replace(&mut self.current_class, ClassType::Class);
+        self.declare(name);
+        self.define(name);
+
+        if let Some(superclass) = superclass {
+            if let Expr::Variable {
+                name: superclass_name,
+                ..
+            } = superclass.as_ref()
+            {
+                if superclass_name.lexeme == name.lexeme {
+                    self.errors.push(SolisError::SelfInheritance {
+                        token: superclass_name.clone(),
+                    });
+                }
+            }
+
+            self.current_class = ClassType::Subclass;
+            self.visit_expr_mut(superclass);
+            self.begin_scope();
+            self.define_str("super");
+        }
+
+        self.begin_scope();
+        self.define_str("this");
+        for method in methods {
+            if let Stmt::Function { name, function, .. } = method {
+                let ty = match name.lexeme.as_str() {
+                    "init" => FunctionType::Initializer,
+                    _ => FunctionType::Method,
+                };
+                self.resolve_function(function, ty);
+            }
+        }
+        self.end_scope();
+
+        if superclass.is_some() {
+            self.end_scope();
+        }
+        self.current_class = enclosing_class;
+    }
+
+    fn visit_return_stmt_mut(
+        &mut self,
+        keyword: &mut Token,
+        value: &mut Option<Box<Expr>>,
+        _: Span,
+    ) {
+        if self.current_function == FunctionType::None {
+            self.errors.push(SolisError::OutsideFunction {
+                token: keyword.clone(),
+            });
+        }
+        let Some(value) = value else {
+            return;
+        };
+        if self.current_function == FunctionType::Initializer {
+            self.errors.push(SolisError::InitializerReturn {
+                token: keyword.clone(),
+            });
+        }
+        self.visit_expr_mut(value)
+    }
+}
+
+impl ExprVisitorMut<()> for Resolver {
+    fn visit_variable_mut(&mut self, name: &mut Token, depth: &mut Option<usize>, _: Span) {
+        if let Some(false) = self.scopes.last().and_then(|s| s.get(&name.lexeme)) {
+            self.errors.push(SolisError::SelfReferencingInitializer {
+                token: name.clone(),
+            });
+        }
+        *depth = self.resolve_local(&name.lexeme);
+    }
+
+    fn visit_assign_mut(
+        &mut self,
+        name: &mut Token,
+        value: &mut Box<Expr>,
+        depth: &mut Option<usize>,
+        _: Span,
+    ) {
+        self.visit_expr_mut(value);
+        *depth = self.resolve_local(&name.lexeme);
+    }
+
+    fn visit_this_mut(&mut self, keyword: &mut Token, depth: &mut Option<usize>, _: Span) {
+        if self.current_class == ClassType::None {
+            self.errors.push(SolisError::OutsideClass {
+                token: keyword.clone(),
+            });
+        }
+        *depth = self.resolve_local("this");
+    }
+
+    fn visit_super_expr_mut(
+        &mut self,
+        keyword: &mut Token,
+        _: &mut Token,
+        depth: &mut Option<usize>,
+        _: Span,
+    ) {
+        match self.current_class {
+            ClassType::None => self.errors.push(SolisError::OutsideClass {
+                token: keyword.clone(),
+            }),
+            ClassType::Class => self.errors.push(SolisError::NoSuperclass {
+                token: keyword.clone(),
+            }),
+            ClassType::Subclass => (),
+        }
+        *depth = self.resolve_local("super");
+    }
+
+    fn visit_lambda_mut(&mut self, _: &mut Token, function: &mut Rc<FunctionDecl>, _: Span) {
+        self.resolve_function(function, FunctionType::Function)
+    }
+}
@@ -0,0 +1,151 @@
+use lexer::Lexer;
+use parser::Parser;
+use resolver::Resolver;
+
+/// Resolves `source`, which must parse, into each error's message and the text it
+/// points at.
+fn resolve(source: &str) -> Vec<(String, &str)> {
+    let (tokens, errors) = Lexer::lex(source);
+    assert!(errors.is_empty(), "{:?}", errors);
+    let (mut statements, errors) = Parser::new(tokens).parse();
+    assert!(errors.is_empty(), "{:?}", errors);
+    Resolver::resolve(&mut statements)
+        .into_iter()
+        .map(|err| {
+            let span = err.span();
+            (err.to_string(), &source[span.start..span.end])
+        })
+        .collect()
+}
+
+/// Asserts that `source` resolves with the single error `message`, pointing at `at`.
+fn assert_error(source: &str, message: &str, at: &str) {
+    assert_eq!(resolve(source), [(message.to_string(), at)], "{}", source);
+}
+
+#[test]
+fn valid_programs_resolve() {
+    let source = r#"
+var a = 1;
+var a = a;
+fn f(x, y = x, ...rest) {
+  var local = x;
+  { var inner = local; var local = inner; }
+  for (var i = 0; i < 3; i = i + 1) { if (i == 1) continue; break; }
+  return |z| z + local;
+}
+class A { init() { return; } get() { return this; } }
+class B < A { get() { return super.get(); } }
+"#;
+    assert_eq!(resolve(source), []);
+}
+
+#[test]
+fn reading_a_local_in_its_own_initializer() {
+    assert_error(
+        "{ var a = 1; { var a = a; } }",
+        "can't read local variable in its own initializer.",
+        "a",
+    );
+}
+
+#[test]
+fn redeclaring_a_local() {
+    assert_error(
+        "{ var a = 1; var a = 2; }",
+        "a variable with this name was already declared in this scope.",
+        "a",
+    );
+    assert_error(
+        "fn f(a, a) {}",
+        "a variable with this name was already declared in this scope.",
+        "a",
+    );
+}
+
+#[test]
+fn break_and_continue_outside_a_loop() {
+    assert_error(
+        "break;",
+        "'break' statement was used outside a loop.",
+        "break",
+    );
+    assert_error(
+        "while (true) { fn f() { continue; } }",
+        "'continue' statement was used outside a loop.",
+        "continue",
+    );
+}
+
+#[test]
+fn return_at_top_level() {
+    assert_error(
+        "return 1;",
+        "'return' statement was used outside a function.",
+        "return",
+    );
+}
+
+#[test]
+fn returning_a_value_from_an_initializer() {
+    assert_error(
+        "class A { init() { return 1; } }",
+        "can't return a value from an initializer.",
+        "return",
+    );
+}
+
+#[test]
+fn this_and_super_outside_a_class() {
+    assert_error("print this;", "'this' was used outside a class.", "this");
+    assert_error(
+        "fn f() { return super.g(); }",
+        "'super' was used outside a class.",
+        "super",
+    );
+}
+
+#[test]
+fn super_without_a_superclass() {
+    assert_error(
+        "class A { f() { return super.f(); } }",
+        "'super' was used in a class with no superclass.",
+        "super",
+    );
+}
+
+#[test]
+fn inheriting_from_itself() {
+    assert_error("class A < A {}", "a class can't inherit from itself.", "A");
+}
+
+#[test]
+fn every_error_is_reported() {
+    let source = r#"
+return;
+fn f() {
+  var a = 1;
+  var a = 2;
+  break;
+}
+print this;
+"#;
+    assert_eq!(
+        resolve(source),
+        [
+            (
+                "'return' statement was used outside a function.".to_string(),
+                "return"
+            ),
+            (
+                "a variable with this name was already declared in this scope.".to_string(),
+                "a"
+            ),
+            (
+                "'break' statement was used outside a loop.".to_string(),
+                "break"
+            ),
+            ("'this' was used outside a class.".to_string(), "this"),
+        ]
+    );
+}
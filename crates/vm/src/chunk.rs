@@ -0,0 +1,129 @@
+use core::*;
+use std::rc::Rc;
+
+/// A single VM instruction. Operands index into the owning [`Chunk`]'s constant pool,
+/// function table or the current frame's local slots; jump operands are absolute
+/// instruction indices.
+#[derive(Debug, Clone, Copy)]
+pub enum OpCode {
+    Constant(u16),
+    Nil,
+    True,
+    False,
+    Pop,
+    /// Duplicates the top two values on the stack, preserving their order.
+    DupTwo,
+
+    GetLocal(u16),
+    SetLocal(u16),
+    GetGlobal(u16),
+    DefineGlobal(u16),
+    SetGlobal(u16),
+    GetUpvalue(u16),
+    SetUpvalue(u16),
+    GetProperty(u16),
+    SetProperty(u16),
+    GetSuper(u16),
+    GetIndex,
+    SetIndex,
+
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    FloorDivide,
+    Not,
+    Negate,
+
+    Print,
+    Jump(u32),
+    JumpIfTrue(u32),
+    JumpIfFalse(u32),
+    JumpIfNotTrue(u32),
+    /// Jumps unless the caller left out the parameter in the given local slot, so its
+    /// default value is only evaluated when needed.
+    JumpIfPassed(u16, u32),
+    Call(u8),
+    /// A call whose last arguments are named, after the chunk's `argument_names` entry.
+    CallNamed {
+        argc: u8,
+        names: u16,
+    },
+    Closure(u16),
+    List(u16),
+    /// Joins the given number of values into a string.
+    Concat(u16),
+    Map(u16),
+    /// Errors unless the value on top of the stack can be used as a map key.
+    CheckKey,
+    CloseUpvalue,
+    Return,
+    Class {
+        name: u16,
+        methods: u16,
+        inherit: bool,
+    },
+}
+
+impl OpCode {
+    /// The token type of the operator an arithmetic or comparison instruction applies.
+    pub fn operator(&self) -> Option<TokenType> {
+        Some(match self {
+            OpCode::Equal => TokenType::EqualEqual,
+            OpCode::NotEqual => TokenType::BangEqual,
+            OpCode::Greater => TokenType::Greater,
+            OpCode::GreaterEqual => TokenType::GreaterEqual,
+            OpCode::Less => TokenType::Less,
+            OpCode::LessEqual => TokenType::LessEqual,
+            OpCode::Add => TokenType::Plus,
+            OpCode::Subtract | OpCode::Negate => TokenType::Minus,
+            OpCode::Multiply => TokenType::Star,
+            OpCode::Divide => TokenType::Slash,
+            OpCode::Modulo => TokenType::Percent,
+            OpCode::FloorDivide => TokenType::TildeSlash,
+            OpCode::Not => TokenType::Bang,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub code: Vec<OpCode>,
+    pub spans: Vec<Span>,
+    pub constants: Vec<ValueWrapper>,
+    pub functions: Vec<Rc<FunctionProto>>,
+    pub argument_names: Vec<Rc<[String]>>,
+}
+
+impl Chunk {
+    pub fn write(&mut self, op: OpCode, span: Span) -> usize {
+        self.code.push(op);
+        self.spans.push(span);
+        self.code.len() - 1
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct UpvalueRef {
+    pub is_local: bool,
+    pub index: u16,
+}
+
+/// The compiled form of a function body, shared by every closure created from it.
+#[derive(Debug, Default)]
+pub struct FunctionProto {
+    pub name: String,
+    pub arity: Arity,
+    /// The names of the parameters, not counting a rest parameter.
+    pub params: Vec<String>,
+    pub chunk: Chunk,
+    pub upvalues: Vec<UpvalueRef>,
+}
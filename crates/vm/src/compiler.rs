@@ -0,0 +1,719 @@
+use crate::chunk::*;
+use core::*;
+use rustc_hash::FxHashMap;
+use std::rc::Rc;
+
+#[derive(Clone, Copy, PartialEq)]
+enum FunctionKind {
+    Script,
+    Function,
+    Method,
+    Initializer,
+}
+
+struct Local {
+    name: String,
+    depth: usize,
+    captured: bool,
+}
+
+struct Loop {
+    depth: usize,
+    breaks: Vec<usize>,
+    /// Jumps to the increment, or back to the condition if there is none.
+    continues: Vec<usize>,
+}
+
+struct FunctionState {
+    function: FunctionProto,
+    kind: FunctionKind,
+    locals: Vec<Local>,
+    scope_depth: usize,
+    loops: Vec<Loop>,
+    identifiers: FxHashMap<String, u16>,
+}
+
+/// Compiles a resolved AST into bytecode. Programs are expected to have passed the
+/// resolver, so misplaced `return`/`break`/`this` are not re-checked here.
+pub struct Compiler {
+    states: Vec<FunctionState>,
+    span: Span,
+}
+
+impl Compiler {
+    pub fn compile(statements: &[Box<Stmt>]) -> Result<Rc<FunctionProto>> {
+        let mut slf = Self {
+            states: Vec::new(),
+            span: Span::default(),
+        };
+        let start = std::time::Instant::now();
+
+        slf.begin_function("script", FunctionKind::Script);
+        for statement in statements {
+            slf.statement(statement)?;
+        }
+        let script = slf.end_function();
+
+        log::debug!(
+            "compiled {} instructions in {:?}",
+            script.chunk.code.len(),
+            start.elapsed()
+        );
+        Ok(Rc::new(script))
+    }
+
+    // - Utils
+
+    fn state(&mut self) -> &mut FunctionState {
+        self.states.last_mut().unwrap()
+    }
+
+    fn chunk(&mut self) -> &mut Chunk {
+        &mut self.state().function.chunk
+    }
+
+    fn emit(&mut self, op: OpCode) -> usize {
+        let span = self.span;
+        self.chunk().write(op, span)
+    }
+
+    fn patch_jump(&mut self, at: usize) {
+        let target = self.chunk().code.len() as u32;
+        match &mut self.chunk().code[at] {
+            OpCode::Jump(to)
+            | OpCode::JumpIfTrue(to)
+            | OpCode::JumpIfFalse(to)
+            | OpCode::JumpIfNotTrue(to)
+            | OpCode::JumpIfPassed(_, to) => *to = target,
+            op => unreachable!("{:?} is not a jump", op),
+        }
+    }
+
+    fn binary_op(operator: &Token) -> Result<OpCode> {
+        Ok(match operator.ty {
+            TokenType::BangEqual => OpCode::NotEqual,
+            TokenType::EqualEqual => OpCode::Equal,
+            TokenType::Greater => OpCode::Greater,
+            TokenType::GreaterEqual => OpCode::GreaterEqual,
+            TokenType::Less => OpCode::Less,
+            TokenType::LessEqual => OpCode::LessEqual,
+            TokenType::Plus => OpCode::Add,
+            TokenType::Minus => OpCode::Subtract,
+            TokenType::Star => OpCode::Multiply,
+            TokenType::Slash => OpCode::Divide,
+            TokenType::Percent => OpCode::Modulo,
+            TokenType::TildeSlash => OpCode::FloorDivide,
+            _ => {
+                return Err(SolisError::RuntimeError(
+                    operator.span,
+                    format!("Unrecognized binary operator `{}`.", operator),
+                ))
+            }
+        })
+    }
+
+    fn too_many(&self, what: &str) -> SolisError {
+        SolisError::RuntimeError(self.span, format!("Too many {} in one function.", what))
+    }
+
+    fn make_constant(&mut self, value: ValueWrapper) -> Result<u16> {
+        let constants = &mut self.chunk().constants;
+        constants.push(value);
+        u16::try_from(constants.len() - 1).map_err(|_| self.too_many("constants"))
+    }
+
+    fn identifier(&mut self, name: &str) -> Result<u16> {
+        if let Some(index) = self.state().identifiers.get(name) {
+            return Ok(*index);
+        }
+
+        let index = self.make_constant(ValueWrapper::Str(name.to_string()))?;
+        self.state().identifiers.insert(name.to_string(), index);
+        Ok(index)
+    }
+
+    fn begin_function(&mut self, name: &str, kind: FunctionKind) {
+        let receiver = match kind {
+            FunctionKind::Method | FunctionKind::Initializer => "this",
+            _ => "",
+        };
+
+        self.states.push(FunctionState {
+            function: FunctionProto {
+                name: name.to_string(),
+                ..Default::default()
+            },
+            kind,
+            locals: vec![Local {
+                name: receiver.to_string(),
+                depth: 0,
+                captured: false,
+            }],
+            scope_depth: 0,
+            loops: Vec::new(),
+            identifiers: FxHashMap::default(),
+        });
+    }
+
+    fn end_function(&mut self) -> FunctionProto {
+        self.emit_implicit_return();
+        self.states.pop().unwrap().function
+    }
+
+    fn emit_implicit_return(&mut self) {
+        match self.state().kind {
+            FunctionKind::Initializer => self.emit(OpCode::GetLocal(0)),
+            _ => self.emit(OpCode::Nil),
+        };
+        self.emit(OpCode::Return);
+    }
+
+    fn begin_scope(&mut self) {
+        self.state().scope_depth += 1;
+    }
+
+    fn end_scope(&mut self) {
+        let state = self.state();
+        state.scope_depth -= 1;
+
+        let depth = state.scope_depth;
+        while let Some(local) = self.state().locals.last() {
+            if local.depth <= depth {
+                break;
+            }
+            let op = match local.captured {
+                true => OpCode::CloseUpvalue,
+                false => OpCode::Pop,
+            };
+            self.emit(op);
+            self.state().locals.pop();
+        }
+    }
+
+    /// Emits the pops for every local declared inside the innermost loop, without
+    /// forgetting them, so `break` and `continue` can leave the loop body early.
+    fn discard_loop_locals(&mut self) {
+        let state = self.state();
+        let depth = state.loops.last().map_or(0, |l| l.depth);
+        let ops: Vec<_> = state
+            .locals
+            .iter()
+            .rev()
+            .take_while(|local| local.depth > depth)
+            .map(|local| match local.captured {
+                true => OpCode::CloseUpvalue,
+                false => OpCode::Pop,
+            })
+            .collect();
+
+        for op in ops {
+            self.emit(op);
+        }
+    }
+
+    fn add_local(&mut self, name: &str) -> Result<()> {
+        let state = self.state();
+        if state.locals.len() > u16::MAX as usize {
+            return Err(self.too_many("local variables"));
+        }
+
+        let depth = state.scope_depth;
+        state.locals.push(Local {
+            name: name.to_string(),
+            depth,
+            captured: false,
+        });
+        Ok(())
+    }
+
+    /// Binds the value on top of the stack to `name`, as a global at the top level
+    /// or as a new local slot otherwise.
+    fn define_variable(&mut self, name: &str) -> Result<()> {
+        if self.state().scope_depth == 0 {
+            let index = self.identifier(name)?;
+            self.emit(OpCode::DefineGlobal(index));
+            return Ok(());
+        }
+
+        self.add_local(name)
+    }
+
+    fn resolve_local(&self, level: usize, name: &str) -> Option<u16> {
+        self.states[level]
+            .locals
+            .iter()
+            .rposition(|local| local.name == name)
+            .map(|slot| slot as u16)
+    }
+
+    fn add_upvalue(&mut self, level: usize, is_local: bool, index: u16) -> Result<u16> {
+        let upvalues = &mut self.states[level].function.upvalues;
+        if let Some(existing) = upvalues
+            .iter()
+            .position(|u| u.is_local == is_local && u.index == index)
+        {
+            return Ok(existing as u16);
+        }
+
+        upvalues.push(UpvalueRef { is_local, index });
+        u16::try_from(upvalues.len() - 1).map_err(|_| self.too_many("closure variables"))
+    }
+
+    fn resolve_upvalue(&mut self, level: usize, name: &str) -> Result<Option<u16>> {
+        if level == 0 {
+            return Ok(None);
+        }
+
+        if let Some(slot) = self.resolve_local(level - 1, name) {
+            self.states[level - 1].locals[slot as usize].captured = true;
+            return self.add_upvalue(level, true, slot).map(Some);
+        }
+
+        match self.resolve_upvalue(level - 1, name)? {
+            Some(index) => self.add_upvalue(level, false, index).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn get_variable(&mut self, name: &str) -> Result<()> {
+        let level = self.states.len() - 1;
+        let op = if let Some(slot) = self.resolve_local(level, name) {
+            OpCode::GetLocal(slot)
+        } else if let Some(index) = self.resolve_upvalue(level, name)? {
+            OpCode::GetUpvalue(index)
+        } else {
+            OpCode::GetGlobal(self.identifier(name)?)
+        };
+
+        self.emit(op);
+        Ok(())
+    }
+
+    fn set_variable(&mut self, name: &str) -> Result<()> {
+        let level = self.states.len() - 1;
+        let op = if let Some(slot) = self.resolve_local(level, name) {
+            OpCode::SetLocal(slot)
+        } else if let Some(index) = self.resolve_upvalue(level, name)? {
+            OpCode::SetUpvalue(index)
+        } else {
+            OpCode::SetGlobal(self.identifier(name)?)
+        };
+
+        self.emit(op);
+        Ok(())
+    }
+
+    // - End: Utils
+
+    fn statement(&mut self, stmt: &Stmt) -> Result<()> {
+        match stmt {
+            Stmt::Expression { expression, .. } => {
+                self.expression(expression)?;
+                self.emit(OpCode::Pop);
+            }
+            Stmt::Print { expression, .. } => {
+                self.expression(expression)?;
+                self.emit(OpCode::Print);
+            }
+            Stmt::Var {
+                name, inititalizer, ..
+            } => {
+                match inititalizer {
+                    Some(inititalizer) => self.expression(inititalizer)?,
+                    None => {
+                        self.emit(OpCode::Nil);
+                    }
+                }
+                self.span = name.span;
+                self.define_variable(&name.lexeme)?;
+            }
+            Stmt::Block { statements, .. } => {
+                self.begin_scope();
+                for statement in statements {
+                    self.statement(statement)?;
+                }
+                self.end_scope();
+            }
+            Stmt::IfStmt {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.expression(condition)?;
+                let then_jump = self.emit(OpCode::JumpIfNotTrue(0));
+                self.emit(OpCode::Pop);
+                self.statement(then_branch)?;
+
+                let else_jump = self.emit(OpCode::Jump(0));
+                self.patch_jump(then_jump);
+                self.emit(OpCode::Pop);
+                if let Some(else_branch) = else_branch {
+                    self.statement(else_branch)?;
+                }
+                self.patch_jump(else_jump);
+            }
+            Stmt::WhileStmt {
+                condition,
+                body,
+                increment,
+                ..
+            } => {
+                let start = self.chunk().code.len();
+                self.expression(condition)?;
+                let exit_jump = self.emit(OpCode::JumpIfNotTrue(0));
+                self.emit(OpCode::Pop);
+
+                let depth = self.state().scope_depth;
+                self.state().loops.push(Loop {
+                    depth,
+                    breaks: Vec::new(),
+                    continues: Vec::new(),
+                });
+                self.statement(body)?;
+
+                for jump in std::mem::take(&mut self.state().loops.last_mut().unwrap().continues) {
+                    self.patch_jump(jump);
+                }
+                if let Some(increment) = increment {
+                    self.expression(increment)?;
+                    self.emit(OpCode::Pop);
+                }
+                self.emit(OpCode::Jump(start as u32));
+
+                self.patch_jump(exit_jump);
+                self.emit(OpCode::Pop);
+                for jump in self.state().loops.pop().unwrap().breaks {
+                    self.patch_jump(jump);
+                }
+            }
+            Stmt::BreakStmt { location, .. } => {
+                self.span = location.span;
+                self.discard_loop_locals();
+                let jump = self.emit(OpCode::Jump(0));
+                if let Some(current) = self.state().loops.last_mut() {
+                    current.breaks.push(jump);
+                }
+            }
+            Stmt::ContinueStmt { location, .. } => {
+                self.span = location.span;
+                self.discard_loop_locals();
+                let jump = self.emit(OpCode::Jump(0));
+                if let Some(current) = self.state().loops.last_mut() {
+                    current.continues.push(jump);
+                }
+            }
+            Stmt::Function { name, function, .. } => {
+                self.span = name.span;
+                let global = self.state().scope_depth == 0;
+                if !global {
+                    self.add_local(&name.lexeme)?;
+                }
+
+                self.function(&name.lexeme, name.span, function, FunctionKind::Function)?;
+                if global {
+                    self.define_variable(&name.lexeme)?;
+                }
+            }
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+                ..
+            } => self.class(name, superclass.as_deref(), methods)?,
+            Stmt::ReturnStmt { keyword, value, .. } => {
+                match value {
+                    Some(value) => self.expression(value)?,
+                    None => {
+                        self.emit(OpCode::Nil);
+                    }
+                }
+                self.span = keyword.span;
+                if self.state().kind == FunctionKind::Initializer {
+                    self.emit(OpCode::Pop);
+                    self.emit(OpCode::GetLocal(0));
+                }
+                self.emit(OpCode::Return);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn function(
+        &mut self,
+        name: &str,
+        span: Span,
+        declaration: &FunctionDecl,
+        kind: FunctionKind,
+    ) -> Result<()> {
+        self.begin_function(name, kind);
+        let function = &mut self.state().function;
+        function.arity = declaration.arity();
+        function.params = declaration
+            .params
+            .iter()
+            .map(|param| param.name.lexeme.clone())
+            .collect();
+
+        self.begin_scope();
+        // Every parameter gets a slot on call, left nil if the caller omitted it. The
+        // defaults are filled in here so each one only sees the parameters before it.
+        for param in &declaration.params {
+            if let Some(default) = &param.default {
+                self.span = param.name.span;
+                let slot = self.state().locals.len() as u16;
+                let skip = self.emit(OpCode::JumpIfPassed(slot, 0));
+                self.expression(default)?;
+                self.emit(OpCode::SetLocal(slot));
+                self.emit(OpCode::Pop);
+                self.patch_jump(skip);
+            }
+            self.add_local(&param.name.lexeme)?;
+        }
+        // The VM packs extra arguments into a list in the slot after the parameters.
+        if let Some(rest) = &declaration.rest {
+            self.add_local(&rest.lexeme)?;
+        }
+        for statement in &declaration.body {
+            self.statement(statement)?;
+        }
+        let function = self.end_function();
+
+        self.span = span;
+        let functions = &mut self.chunk().functions;
+        functions.push(Rc::new(function));
+        let index = u16::try_from(functions.len() - 1).map_err(|_| self.too_many("functions"))?;
+        self.emit(OpCode::Closure(index));
+        Ok(())
+    }
+
+    fn class(&mut self, name: &Token, superclass: Option<&Expr>, methods: &[Stmt]) -> Result<()> {
+        self.span = name.span;
+        let count = u16::try_from(methods.len()).map_err(|_| self.too_many("methods"))?;
+        let global = self.state().scope_depth == 0;
+        let slot = self.state().locals.len() as u16;
+        if !global {
+            self.emit(OpCode::Nil);
+            self.add_local(&name.lexeme)?;
+        }
+
+        if let Some(superclass) = superclass {
+            self.expression(superclass)?;
+            self.begin_scope();
+            self.add_local("super")?;
+        }
+
+        for method in methods {
+            if let Stmt::Function { name, function, .. } = method {
+                let kind = match name.lexeme.as_str() {
+                    "init" => FunctionKind::Initializer,
+                    _ => FunctionKind::Method,
+                };
+                self.function(&name.lexeme, name.span, function, kind)?;
+            }
+        }
+
+        self.span = superclass.map_or(name.span, Expr::span);
+        let index = self.identifier(&name.lexeme)?;
+        self.emit(OpCode::Class {
+            name: index,
+            methods: count,
+            inherit: superclass.is_some(),
+        });
+
+        if global {
+            self.emit(OpCode::DefineGlobal(index));
+        } else {
+            self.emit(OpCode::SetLocal(slot));
+            self.emit(OpCode::Pop);
+        }
+
+        if superclass.is_some() {
+            self.end_scope();
+        }
+        Ok(())
+    }
+
+    fn expression(&mut self, expr: &Expr) -> Result<()> {
+        match expr {
+            Expr::Literal { value, .. } => {
+                match value {
+                    ValueWrapper::Nil => self.emit(OpCode::Nil),
+                    ValueWrapper::Bool(true) => self.emit(OpCode::True),
+                    ValueWrapper::Bool(false) => self.emit(OpCode::False),
+                    value => {
+                        let index = self.make_constant(value.clone())?;
+                        self.emit(OpCode::Constant(index))
+                    }
+                };
+            }
+            Expr::Grouping { expression, .. } => self.expression(expression)?,
+            Expr::Unary {
+                operator, right, ..
+            } => {
+                self.expression(right)?;
+                self.span = expr.span();
+                match operator.ty {
+                    TokenType::Minus => self.emit(OpCode::Negate),
+                    _ => self.emit(OpCode::Not),
+                };
+            }
+            Expr::Binary {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                self.expression(left)?;
+                self.expression(right)?;
+                self.span = expr.span();
+                let op = Self::binary_op(operator)?;
+                self.emit(op);
+            }
+            Expr::Logical {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                self.expression(left)?;
+                self.span = operator.span;
+                let end_jump = match operator.ty {
+                    TokenType::Or => self.emit(OpCode::JumpIfTrue(0)),
+                    _ => self.emit(OpCode::JumpIfFalse(0)),
+                };
+                self.emit(OpCode::Pop);
+                self.expression(right)?;
+                self.patch_jump(end_jump);
+            }
+            Expr::Variable { name, .. } => {
+                self.span = name.span;
+                self.get_variable(&name.lexeme)?;
+            }
+            Expr::Assign { name, value, .. } => {
+                self.expression(value)?;
+                self.span = name.span;
+                self.set_variable(&name.lexeme)?;
+            }
+            Expr::Call {
+                callee,
+                args,
+                named,
+                ..
+            } => {
+                self.expression(callee)?;
+                for argument in args.iter().chain(named.iter().map(|(_, value)| value)) {
+                    self.expression(argument)?;
+                }
+                self.span = expr.span();
+                let argc = (args.len() + named.len()) as u8;
+                if named.is_empty() {
+                    self.emit(OpCode::Call(argc));
+                    return Ok(());
+                }
+
+                let names = named.iter().map(|(name, _)| name.lexeme.clone()).collect();
+                let argument_names = &mut self.chunk().argument_names;
+                argument_names.push(names);
+                let names = u16::try_from(argument_names.len() - 1)
+                    .map_err(|_| self.too_many("calls with named arguments"))?;
+                self.emit(OpCode::CallNamed { argc, names });
+            }
+            Expr::Get { object, name, .. } => {
+                self.expression(object)?;
+                self.span = name.span;
+                let index = self.identifier(&name.lexeme)?;
+                self.emit(OpCode::GetProperty(index));
+            }
+            Expr::Set {
+                object,
+                name,
+                value,
+                ..
+            } => {
+                self.expression(object)?;
+                self.expression(value)?;
+                self.span = name.span;
+                let index = self.identifier(&name.lexeme)?;
+                self.emit(OpCode::SetProperty(index));
+            }
+            Expr::This { keyword, .. } => {
+                self.span = keyword.span;
+                self.get_variable("this")?;
+            }
+            Expr::SuperExpr {
+                keyword, method, ..
+            } => {
+                self.span = keyword.span;
+                self.get_variable("this")?;
+                self.get_variable("super")?;
+                let index = self.identifier(&method.lexeme)?;
+                self.emit(OpCode::GetSuper(index));
+            }
+            Expr::List { elements, .. } => {
+                for element in elements {
+                    self.expression(element)?;
+                }
+                self.span = expr.span();
+                let count =
+                    u16::try_from(elements.len()).map_err(|_| self.too_many("list elements"))?;
+                self.emit(OpCode::List(count));
+            }
+            Expr::Interpolation { parts, .. } => {
+                for part in parts {
+                    self.expression(part)?;
+                }
+                self.span = expr.span();
+                let count = u16::try_from(parts.len())
+                    .map_err(|_| self.too_many("interpolated segments"))?;
+                self.emit(OpCode::Concat(count));
+            }
+            Expr::Map { entries, .. } => {
+                for (key, value) in entries {
+                    self.expression(key)?;
+                    self.span = key.span();
+                    self.emit(OpCode::CheckKey);
+                    self.expression(value)?;
+                }
+                self.span = expr.span();
+                let count =
+                    u16::try_from(entries.len()).map_err(|_| self.too_many("map entries"))?;
+                self.emit(OpCode::Map(count));
+            }
+            Expr::Index { object, index, .. } => {
+                self.expression(object)?;
+                self.expression(index)?;
+                self.span = expr.span();
+                self.emit(OpCode::GetIndex);
+            }
+            Expr::SetIndex {
+                object,
+                index,
+                value,
+                operator,
+                ..
+            } => {
+                self.expression(object)?;
+                self.expression(index)?;
+                if let Some(operator) = operator {
+                    self.span = expr.span();
+                    self.emit(OpCode::DupTwo);
+                    self.emit(OpCode::GetIndex);
+                    self.expression(value)?;
+                    self.span = expr.span();
+                    self.emit(Self::binary_op(operator)?);
+                } else {
+                    self.expression(value)?;
+                }
+                self.span = expr.span();
+                self.emit(OpCode::SetIndex);
+            }
+            Expr::Lambda {
+                keyword, function, ..
+            } => self.function("lambda", keyword.span, function, FunctionKind::Function)?,
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,687 @@
+pub mod chunk;
+pub mod compiler;
+pub mod objects;
+
+use crate::{
+    chunk::{FunctionProto, OpCode},
+    compiler::Compiler,
+    objects::*,
+};
+use core::{ValueWrapper::*, *};
+use log::debug;
+use rustc_hash::FxHashMap;
+use std::{
+    any::Any,
+    cell::RefCell,
+    rc::{Rc, Weak},
+};
+
+const FRAMES_MAX: usize = 4096;
+
+/// Where a VM keeps its state while it isn't running bytecode: between calls to
+/// [`Vm::interpret`], and while a native function runs. Closures point back to it so
+/// that the host can call them through the VM that created them.
+pub(crate) type Home = RefCell<Option<VmState>>;
+
+struct CallFrame {
+    closure: Rc<Closure>,
+    ip: usize,
+    base: usize,
+    /// The local slots of parameters the caller left out, to be given their default.
+    missing: Vec<u16>,
+}
+
+pub struct Vm {
+    home: Rc<Home>,
+}
+
+pub(crate) struct VmState {
+    stack: Vec<ValueWrapper>,
+    frames: Vec<CallFrame>,
+    globals: FxHashMap<String, ValueWrapper>,
+    open_upvalues: Vec<RcCell<Upvalue>>,
+    home: Weak<Home>,
+}
+
+impl Vm {
+    pub fn new() -> Self {
+        let home = Rc::new_cyclic(|home: &Weak<Home>| {
+            RefCell::new(Some(VmState {
+                stack: Vec::with_capacity(256),
+                frames: Vec::with_capacity(64),
+                globals: globals(),
+                open_upvalues: Vec::new(),
+                home: home.clone(),
+            }))
+        });
+        Self { home }
+    }
+
+    /// Runs `f` on the VM's state, which is always home while the host holds the VM.
+    fn state<T>(&mut self, f: impl FnOnce(&mut VmState) -> T) -> T {
+        let mut state = self.home.borrow_mut();
+        f(state.as_mut().expect("the VM isn't running"))
+    }
+
+    /// Defines (or redefines) the global variable `name`.
+    pub fn define_global<T: Into<ValueWrapper>>(&mut self, name: &str, value: T) {
+        let value = value.into();
+        self.state(|state| state.globals.insert(name.to_string(), value));
+    }
+
+    /// Exposes a Rust closure to scripts as the global function `name`. The backend
+    /// checks the arity before calling it; argument types can be checked with
+    /// [`Arguments::get`].
+    pub fn register_fn<A, F>(&mut self, name: &str, arity: A, function: F)
+    where
+        A: Into<Arity>,
+        F: Fn(Arguments) -> Result<ValueWrapper> + 'static,
+    {
+        self.define_global(name, Func(NativeFunction::new(name, arity, function)));
+    }
+
+    pub fn interpret(&mut self, statements: Vec<Box<Stmt>>) -> Result<()> {
+        let function = Compiler::compile(&statements)?;
+        let mut state = self.home.take().expect("the VM isn't running");
+        let result = state.interpret(function);
+        *self.home.borrow_mut() = Some(state);
+        result
+    }
+}
+
+impl VmState {
+    fn interpret(&mut self, function: Rc<FunctionProto>) -> Result<()> {
+        let closure = Rc::new(Closure {
+            function,
+            upvalues: Vec::new(),
+            vm: self.home.clone(),
+        });
+
+        self.stack.push(Func(closure.clone()));
+        self.frames.push(CallFrame {
+            closure,
+            ip: 0,
+            base: 0,
+            missing: Vec::new(),
+        });
+
+        let start = std::time::Instant::now();
+        let result = self.run(0).map(|_| ());
+        debug!("executed script in {:?}", start.elapsed());
+
+        if result.is_err() {
+            self.stack.clear();
+            self.frames.clear();
+            self.open_upvalues.clear();
+        }
+        result
+    }
+
+    /// A VM with nothing allocated, to stand in for one that's gone home.
+    fn empty(home: Weak<Home>) -> Self {
+        Self {
+            stack: Vec::new(),
+            frames: Vec::new(),
+            globals: FxHashMap::default(),
+            open_upvalues: Vec::new(),
+            home,
+        }
+    }
+
+    /// Sends the state home while `f` runs, so that closures can call back into it.
+    fn lend<T>(&mut self, f: impl FnOnce() -> T) -> T {
+        let home = self.home.upgrade().expect("the VM outlives its run");
+        let empty = Self::empty(self.home.clone());
+        *home.borrow_mut() = Some(std::mem::replace(self, empty));
+        let result = f();
+        *self = home.take().expect("the lent VM was returned");
+        result
+    }
+
+    /// Calls `closure` on the VM that created it, with `receiver` as `this`. This is how
+    /// the host, or a native function, calls a script function it was given.
+    pub(crate) fn call_at_home(
+        receiver: Option<ValueWrapper>,
+        closure: Rc<Closure>,
+        arguments: Vec<ValueWrapper>,
+        named: Vec<(String, ValueWrapper)>,
+    ) -> Result<ValueWrapper> {
+        let error = |reason: &str| {
+            Err(SolisError::RuntimeError(
+                Span::default(),
+                format!("Function `{}` {}.", closure.function.name, reason),
+            ))
+        };
+        let Some(home) = closure.vm.upgrade() else {
+            return error("can't be called once its VM is dropped");
+        };
+        let Some(mut state) = home.take() else {
+            return error("can't be called while its VM is running");
+        };
+        let result = state.call_nested(receiver, closure, arguments, named);
+        *home.borrow_mut() = Some(state);
+        result
+    }
+
+    /// Whether `closure` was created by this VM, so that it can run in its frames.
+    fn owns(&self, closure: &Closure) -> bool {
+        self.home.ptr_eq(&closure.vm)
+    }
+
+    /// Runs `closure` until it returns, on top of whatever the VM is already running.
+    /// If it fails, everything it left on the stack is dropped so the VM can carry on.
+    fn call_nested(
+        &mut self,
+        receiver: Option<ValueWrapper>,
+        closure: Rc<Closure>,
+        arguments: Vec<ValueWrapper>,
+        named: Vec<(String, ValueWrapper)>,
+    ) -> Result<ValueWrapper> {
+        let depth = self.frames.len();
+        let height = self.stack.len();
+        let argc = arguments.len() + named.len();
+        let names: Option<Rc<[String]>> = match named.is_empty() {
+            true => None,
+            false => Some(named.iter().map(|(name, _)| name.clone()).collect()),
+        };
+
+        self.stack
+            .push(receiver.unwrap_or_else(|| Func(closure.clone())));
+        self.stack.extend(arguments);
+        self.stack.extend(named.into_iter().map(|(_, value)| value));
+        let result = self
+            .call_closure(closure, argc, names)
+            .and_then(|()| self.run(depth));
+        if result.is_err() {
+            self.close_upvalues(height);
+            self.frames.truncate(depth);
+            self.stack.truncate(height);
+        }
+        result
+    }
+
+    // - Utils
+
+    fn frame(&self) -> &CallFrame {
+        self.frames.last().unwrap()
+    }
+
+    fn span(&self) -> Span {
+        let frame = self.frame();
+        frame.closure.function.chunk.spans[frame.ip - 1]
+    }
+
+    fn error<T>(&self, message: String) -> Result<T> {
+        Err(SolisError::RuntimeError(self.span(), message))
+    }
+
+    fn name(&self, index: u16) -> &str {
+        match &self.frame().closure.function.chunk.constants[index as usize] {
+            Str(name) => name,
+            _ => unreachable!("identifier constants are always strings"),
+        }
+    }
+
+    fn pop(&mut self) -> ValueWrapper {
+        self.stack.pop().unwrap()
+    }
+
+    fn peek(&self) -> &ValueWrapper {
+        self.stack.last().unwrap()
+    }
+
+    fn capture_upvalue(&mut self, slot: usize) -> RcCell<Upvalue> {
+        for upvalue in &self.open_upvalues {
+            if let Upvalue::Open(open) = *upvalue.borrow() {
+                if open == slot {
+                    return upvalue.clone();
+                }
+            }
+        }
+
+        let upvalue = Rc::new(RefCell::new(Upvalue::Open(slot)));
+        self.open_upvalues.push(upvalue.clone());
+        upvalue
+    }
+
+    fn close_upvalues(&mut self, from: usize) {
+        let stack = &self.stack;
+        self.open_upvalues.retain(|upvalue| {
+            let mut upvalue = upvalue.borrow_mut();
+            match *upvalue {
+                Upvalue::Open(slot) if slot >= from => {
+                    *upvalue = Upvalue::Closed(stack[slot].clone());
+                    false
+                }
+                _ => true,
+            }
+        });
+    }
+
+    // - End: Utils
+
+    fn check_arity(&self, expected: Arity, argc: usize) -> Result<()> {
+        if !expected.accepts(argc) {
+            return self.error(format!(
+                "Expected {} arguments but received {} arguments.",
+                expected, argc
+            ));
+        }
+        Ok(())
+    }
+
+    /// Splits the trailing named arguments off the `arguments` of a call.
+    fn named_arguments(
+        arguments: &mut Vec<ValueWrapper>,
+        names: Option<Rc<[String]>>,
+    ) -> Vec<(String, ValueWrapper)> {
+        let Some(names) = names else {
+            return Vec::new();
+        };
+        let values = arguments.split_off(arguments.len() - names.len());
+        names.iter().cloned().zip(values).collect()
+    }
+
+    fn call_closure(
+        &mut self,
+        closure: Rc<Closure>,
+        argc: usize,
+        names: Option<Rc<[String]>>,
+    ) -> Result<()> {
+        let function = &closure.function;
+        self.check_arity(function.arity, argc)?;
+        if self.frames.len() >= FRAMES_MAX {
+            return self.error(String::from("Stack overflow."));
+        }
+
+        // Lay the arguments out one slot per parameter, unless they already are.
+        let variadic = function.arity.is_variadic();
+        let mut missing = Vec::new();
+        if names.is_some() || variadic || argc != function.params.len() {
+            let mut arguments = self.stack.split_off(self.stack.len() - argc);
+            let named = Self::named_arguments(&mut arguments, names);
+            let params = function.params.iter().map(String::as_str);
+            let (values, extra) =
+                bind_arguments(&function.name, params, function.arity.min, arguments, named)
+                    .map_err(|err| err.or_span(self.span()))?;
+
+            for (slot, value) in (1..).zip(values) {
+                if value.is_none() {
+                    missing.push(slot);
+                }
+                self.stack.push(value.unwrap_or(Nil));
+            }
+            if variadic {
+                self.stack.push(List(Rc::new(RefCell::new(extra))));
+            }
+        }
+
+        let slots = function.params.len() + variadic as usize;
+        self.frames.push(CallFrame {
+            ip: 0,
+            base: self.stack.len() - slots - 1,
+            closure,
+            missing,
+        });
+        Ok(())
+    }
+
+    fn call_native(
+        &mut self,
+        function: Rc<dyn Callable>,
+        argc: usize,
+        names: Option<Rc<[String]>>,
+    ) -> Result<()> {
+        self.check_arity(function.arity(), argc)?;
+        let mut arguments = self.stack.split_off(self.stack.len() - argc);
+        let named = Self::named_arguments(&mut arguments, names);
+        self.stack.pop();
+
+        let result = self
+            .lend(|| function.call_named(arguments, named))
+            .map_err(|err| err.or_span(self.span()))?;
+        self.stack.push(result);
+        Ok(())
+    }
+
+    fn call_value(&mut self, argc: usize, names: Option<Rc<[String]>>) -> Result<()> {
+        let slot = self.stack.len() - argc - 1;
+        match self.stack[slot].clone() {
+            Func(function) => {
+                let any: Rc<dyn Any> = function.clone();
+                let any = match any.downcast::<Closure>() {
+                    Ok(closure) if self.owns(&closure) => {
+                        return self.call_closure(closure, argc, names)
+                    }
+                    Ok(_) => return self.call_native(function, argc, names),
+                    Err(any) => any,
+                };
+
+                match any.downcast::<BoundMethod>() {
+                    Ok(bound) if self.owns(&bound.method) => {
+                        self.stack[slot] = bound.receiver.clone();
+                        return self.call_closure(bound.method.clone(), argc, names);
+                    }
+                    _ => (),
+                }
+
+                self.call_native(function, argc, names)
+            }
+            ValueWrapper::Class(class) => {
+                self.check_arity(class.arity(), argc)?;
+                let instance = Instance(SolisInstance::new(class.clone()));
+                self.stack[slot] = instance.clone();
+
+                let Some(init) = class.find_method("init") else {
+                    return Ok(());
+                };
+                let any: Rc<dyn Any> = init.clone();
+                match any.downcast::<Closure>() {
+                    Ok(closure) if self.owns(&closure) => self.call_closure(closure, argc, names),
+                    _ => {
+                        self.call_native(init.bind(instance.clone()), argc, names)?;
+                        *self.stack.last_mut().unwrap() = instance;
+                        Ok(())
+                    }
+                }
+            }
+            _ => self.error(String::from("Can only call functions and classes.")),
+        }
+    }
+
+    fn binary(&mut self, op: OpCode) -> Result<()> {
+        let right = self.pop();
+        let left = self.pop();
+
+        let result = match (op, &left, &right) {
+            (OpCode::Add, Num(l), Num(r)) => Num(l + r),
+            (OpCode::Subtract, Num(l), Num(r)) => Num(l - r),
+            (OpCode::Multiply, Num(l), Num(r)) => Num(l * r),
+            (OpCode::Divide, Num(l), Num(r)) => Num(l / r),
+            (OpCode::Less, Num(l), Num(r)) => Bool(l < r),
+            (OpCode::LessEqual, Num(l), Num(r)) => Bool(l <= r),
+            (OpCode::Greater, Num(l), Num(r)) => Bool(l > r),
+            (OpCode::GreaterEqual, Num(l), Num(r)) => Bool(l >= r),
+            (OpCode::Add, Int(l), Int(r)) if let Some(sum) = l.checked_add(*r) => Int(sum),
+            (OpCode::Subtract, Int(l), Int(r)) if let Some(diff) = l.checked_sub(*r) => Int(diff),
+            (OpCode::Less, Int(l), Int(r)) => Bool(l < r),
+            (OpCode::LessEqual, Int(l), Int(r)) => Bool(l <= r),
+            (OpCode::Greater, Int(l), Int(r)) => Bool(l > r),
+            (OpCode::GreaterEqual, Int(l), Int(r)) => Bool(l >= r),
+            _ => {
+                let operator = op.operator().unwrap();
+                let result = left.binary(&operator, &right);
+                match result.map_err(|err| err.or_span(self.span()))? {
+                    Some(result) => result,
+                    None => {
+                        return self.error(format!(
+                            "Invalid equation: {:?} `{}` {:?}",
+                            left,
+                            operator.symbol(),
+                            right
+                        ))
+                    }
+                }
+            }
+        };
+
+        self.stack.push(result);
+        Ok(())
+    }
+
+    /// Runs until the frame count drops back to `depth`, returning the value the last
+    /// frame returned.
+    fn run(&mut self, depth: usize) -> Result<ValueWrapper> {
+        loop {
+            let frame = self.frames.last_mut().unwrap();
+            let op = frame.closure.function.chunk.code[frame.ip];
+            frame.ip += 1;
+
+            match op {
+                OpCode::Constant(index) => {
+                    let value =
+                        self.frame().closure.function.chunk.constants[index as usize].clone();
+                    self.stack.push(value);
+                }
+                OpCode::Nil => self.stack.push(Nil),
+                OpCode::True => self.stack.push(Bool(true)),
+                OpCode::False => self.stack.push(Bool(false)),
+                OpCode::DupTwo => {
+                    let len = self.stack.len();
+                    self.stack.extend_from_within(len - 2..);
+                }
+                OpCode::Pop => {
+                    self.pop();
+                }
+
+                OpCode::GetLocal(slot) => {
+                    let value = self.stack[self.frame().base + slot as usize].clone();
+                    self.stack.push(value);
+                }
+                OpCode::SetLocal(slot) => {
+                    let slot = self.frame().base + slot as usize;
+                    self.stack[slot] = self.peek().clone();
+                }
+                OpCode::GetGlobal(index) => {
+                    let name = self.name(index);
+                    match self.globals.get(name) {
+                        Some(value) => self.stack.push(value.clone()),
+                        None => return self.error(format!("Unexpected variable name `{}`.", name)),
+                    }
+                }
+                OpCode::DefineGlobal(index) => {
+                    let value = self.pop();
+                    let name = self.name(index).to_string();
+                    self.globals.insert(name, value);
+                }
+                OpCode::SetGlobal(index) => {
+                    let value = self.peek().clone();
+                    let frame = self.frames.last().unwrap();
+                    let Str(name) = &frame.closure.function.chunk.constants[index as usize] else {
+                        unreachable!("identifier constants are always strings")
+                    };
+                    match self.globals.get_mut(name) {
+                        Some(slot) => *slot = value,
+                        None => return self.error(format!("Undefined variable `{}`.", name)),
+                    }
+                }
+                OpCode::GetUpvalue(index) => {
+                    let value = match &*self.frame().closure.upvalues[index as usize].borrow() {
+                        Upvalue::Open(slot) => self.stack[*slot].clone(),
+                        Upvalue::Closed(value) => value.clone(),
+                    };
+                    self.stack.push(value);
+                }
+                OpCode::SetUpvalue(index) => {
+                    let value = self.peek().clone();
+                    let frame = self.frames.last().unwrap();
+                    match &mut *frame.closure.upvalues[index as usize].borrow_mut() {
+                        Upvalue::Open(slot) => self.stack[*slot] = value,
+                        Upvalue::Closed(closed) => *closed = value,
+                    };
+                }
+                OpCode::GetProperty(index) => {
+                    let Instance(instance) = self.pop() else {
+                        return self.error(String::from("Only instances have properties."));
+                    };
+                    let value = SolisInstance::get(&instance, self.name(index), self.span())?;
+                    self.stack.push(value);
+                }
+                OpCode::SetProperty(index) => {
+                    let value = self.pop();
+                    let Instance(instance) = self.pop() else {
+                        return self.error(String::from("Only instances have fields."));
+                    };
+                    instance.borrow_mut().set(self.name(index), value.clone());
+                    self.stack.push(value);
+                }
+                OpCode::GetIndex => {
+                    let index = self.pop();
+                    let object = self.pop();
+                    self.stack.push(object.get_index(&index, self.span())?);
+                }
+                OpCode::SetIndex => {
+                    let value = self.pop();
+                    let index = self.pop();
+                    let object = self.pop();
+                    object.set_index(&index, value.clone(), self.span())?;
+                    self.stack.push(value);
+                }
+                OpCode::GetSuper(index) => {
+                    let ValueWrapper::Class(superclass) = self.pop() else {
+                        unreachable!("`super` always refers to a class")
+                    };
+                    let this = self.pop();
+                    let name = self.name(index);
+                    match superclass.find_method(name) {
+                        Some(method) => self.stack.push(Func(method.bind(this))),
+                        None => return self.error(format!("Undefined property `{}`.", name)),
+                    }
+                }
+
+                OpCode::Equal
+                | OpCode::NotEqual
+                | OpCode::Greater
+                | OpCode::GreaterEqual
+                | OpCode::Less
+                | OpCode::LessEqual
+                | OpCode::Add
+                | OpCode::Subtract
+                | OpCode::Multiply
+                | OpCode::Divide
+                | OpCode::Modulo
+                | OpCode::FloorDivide => self.binary(op)?,
+                OpCode::Not | OpCode::Negate => {
+                    let operator = op.operator().unwrap();
+                    let right = self.pop();
+                    let result = right.unary(&operator);
+                    match result.map_err(|err| err.or_span(self.span()))? {
+                        Some(value) => self.stack.push(value),
+                        None => {
+                            return self.error(format!(
+                                "Unrecognized operator `{}` with value `{}`.",
+                                operator.symbol(),
+                                right
+                            ))
+                        }
+                    }
+                }
+
+                OpCode::Print => println!("{}", self.pop()),
+                OpCode::Jump(target) => self.frames.last_mut().unwrap().ip = target as usize,
+                OpCode::JumpIfTrue(target) => {
+                    if *self.peek() == Bool(true) {
+                        self.frames.last_mut().unwrap().ip = target as usize;
+                    }
+                }
+                OpCode::JumpIfFalse(target) => {
+                    if *self.peek() == Bool(false) {
+                        self.frames.last_mut().unwrap().ip = target as usize;
+                    }
+                }
+                OpCode::JumpIfNotTrue(target) => {
+                    if *self.peek() != Bool(true) {
+                        self.frames.last_mut().unwrap().ip = target as usize;
+                    }
+                }
+                OpCode::JumpIfPassed(slot, target) => {
+                    let frame = self.frames.last_mut().unwrap();
+                    if !frame.missing.contains(&slot) {
+                        frame.ip = target as usize;
+                    }
+                }
+                OpCode::Call(argc) => self.call_value(argc as usize, None)?,
+                OpCode::CallNamed { argc, names } => {
+                    let chunk = &self.frame().closure.function.chunk;
+                    let names = chunk.argument_names[names as usize].clone();
+                    self.call_value(argc as usize, Some(names))?
+                }
+                OpCode::Closure(index) => {
+                    let frame = self.frame();
+                    let function = frame.closure.function.chunk.functions[index as usize].clone();
+                    let base = frame.base;
+
+                    let mut upvalues = Vec::with_capacity(function.upvalues.len());
+                    for upvalue in &function.upvalues {
+                        upvalues.push(match upvalue.is_local {
+                            true => self.capture_upvalue(base + upvalue.index as usize),
+                            false => self.frame().closure.upvalues[upvalue.index as usize].clone(),
+                        });
+                    }
+
+                    self.stack.push(Func(Rc::new(Closure {
+                        function,
+                        upvalues,
+                        vm: self.home.clone(),
+                    })));
+                }
+                OpCode::List(count) => {
+                    let elements = self.stack.split_off(self.stack.len() - count as usize);
+                    self.stack.push(List(Rc::new(RefCell::new(elements))));
+                }
+                OpCode::Concat(count) => {
+                    let parts = self.stack.split_off(self.stack.len() - count as usize);
+                    let string = parts.iter().map(ValueWrapper::stringify).collect();
+                    self.stack.push(Str(string));
+                }
+                OpCode::Map(count) => {
+                    let entries = self.stack.split_off(self.stack.len() - 2 * count as usize);
+                    let mut map = SolisMap::default();
+                    for entry in entries.chunks_exact(2) {
+                        map.insert(MapKey::new(&entry[0], self.span())?, entry[1].clone());
+                    }
+                    self.stack.push(Map(Rc::new(RefCell::new(map))));
+                }
+                OpCode::CheckKey => {
+                    MapKey::new(self.peek(), self.span())?;
+                }
+                OpCode::CloseUpvalue => {
+                    self.close_upvalues(self.stack.len() - 1);
+                    self.pop();
+                }
+                OpCode::Return => {
+                    let result = self.pop();
+                    let frame = self.frames.pop().unwrap();
+                    self.close_upvalues(frame.base);
+                    self.stack.truncate(frame.base);
+
+                    if self.frames.len() == depth {
+                        return Ok(result);
+                    }
+                    self.stack.push(result);
+                }
+                OpCode::Class {
+                    name,
+                    methods,
+                    inherit,
+                } => {
+                    let closures = self.stack.split_off(self.stack.len() - methods as usize);
+                    let superclass = match inherit {
+                        true => match self.peek() {
+                            ValueWrapper::Class(superclass) => Some(superclass.clone()),
+                            _ => return self.error(String::from("Superclass must be a class.")),
+                        },
+                        false => None,
+                    };
+
+                    let mut class_methods = FxHashMap::default();
+                    for closure in closures {
+                        if let Func(method) = closure {
+                            class_methods.insert(method.name().to_string(), method);
+                        }
+                    }
+
+                    let class =
+                        SolisClass::new(self.name(name).to_string(), superclass, class_methods);
+                    self.stack.push(ValueWrapper::Class(class));
+                }
+            }
+        }
+    }
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,82 @@
+use crate::{chunk::FunctionProto, Home, VmState};
+use core::*;
+use std::rc::{Rc, Weak};
+
+/// A variable captured by a closure. It points into the VM stack while the declaring
+/// function is still running and owns the value once that slot goes out of scope.
+#[derive(Debug)]
+pub enum Upvalue {
+    Open(usize),
+    Closed(ValueWrapper),
+}
+
+#[derive(Debug, Clone)]
+pub struct Closure {
+    pub function: Rc<FunctionProto>,
+    pub upvalues: Vec<RcCell<Upvalue>>,
+    /// The VM that created the closure, and whose stack its open upvalues point into.
+    pub(crate) vm: Weak<Home>,
+}
+
+impl Callable for Closure {
+    fn name(&self) -> &str {
+        &self.function.name
+    }
+
+    fn arity(&self) -> Arity {
+        self.function.arity
+    }
+
+    fn call(&self, arguments: Vec<ValueWrapper>) -> Result<ValueWrapper> {
+        self.call_named(arguments, Vec::new())
+    }
+
+    /// Runs on the VM that created the closure.
+    fn call_named(
+        &self,
+        arguments: Vec<ValueWrapper>,
+        named: Vec<(String, ValueWrapper)>,
+    ) -> Result<ValueWrapper> {
+        VmState::call_at_home(None, Rc::new(self.clone()), arguments, named)
+    }
+
+    fn bind(self: Rc<Self>, instance: ValueWrapper) -> Rc<dyn Callable> {
+        Rc::new(BoundMethod {
+            receiver: instance,
+            method: self,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct BoundMethod {
+    pub receiver: ValueWrapper,
+    pub method: Rc<Closure>,
+}
+
+impl Callable for BoundMethod {
+    fn name(&self) -> &str {
+        self.method.name()
+    }
+
+    fn arity(&self) -> Arity {
+        self.method.arity()
+    }
+
+    fn call(&self, arguments: Vec<ValueWrapper>) -> Result<ValueWrapper> {
+        self.call_named(arguments, Vec::new())
+    }
+
+    fn call_named(
+        &self,
+        arguments: Vec<ValueWrapper>,
+        named: Vec<(String, ValueWrapper)>,
+    ) -> Result<ValueWrapper> {
+        let receiver = Some(self.receiver.clone());
+        VmState::call_at_home(receiver, self.method.clone(), arguments, named)
+    }
+
+    fn bind(self: Rc<Self>, instance: ValueWrapper) -> Rc<dyn Callable> {
+        self.method.clone().bind(instance)
+    }
+}